@@ -0,0 +1,105 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/*
+ * #[derive(FromRow)] for structs with named fields
+ * Each field is read with ResultSet::get from the column with the same name.
+ * Field attributes:
+ *   #[freetds(rename = "column")]  read from another column
+ *   #[freetds(default)]            use Default::default() when the column is null or absent
+ * Nullable columns are mapped to Option<T> fields.
+ */
+#[proc_macro_derive(FromRow, attributes(freetds))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match from_row(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct FieldAttrs {
+    rename: Option<String>,
+    default: bool,
+}
+
+fn field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs {
+        rename: None,
+        default: false,
+    };
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("freetds")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                attrs.rename = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                attrs.default = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported freetds attribute, expected rename or default"))
+            }
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "FromRow can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut values = Vec::with_capacity(fields.len());
+    for field in fields {
+        let ident = field.ident.as_ref().expect("Unexpected unnamed field");
+        let ty = &field.ty;
+        let attrs = field_attrs(field)?;
+        let column = attrs
+            .rename
+            .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+
+        let value = if attrs.default {
+            quote! {
+                #ident: if rs.has_column(#column) {
+                    rs.get::<::std::option::Option<#ty>>(#column)?.unwrap_or_default()
+                } else {
+                    ::std::default::Default::default()
+                }
+            }
+        } else {
+            quote! {
+                #ident: rs.get::<#ty>(#column)?
+            }
+        };
+        values.push(value);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::freetds::FromRow for #name #ty_generics #where_clause {
+            fn from_row(rs: &mut ::freetds::ResultSet) -> ::freetds::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#values,)*
+                })
+            }
+        }
+    })
+}
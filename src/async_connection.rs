@@ -0,0 +1,229 @@
+use crate::connection::ConnectionBuilder;
+use crate::to_sql::ToSql;
+use crate::{Connection, Error, ResultSet, Result, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/*
+ * A connection whose calls return futures instead of blocking
+ * Every CT-Library call runs on a worker thread dedicated to the connection,
+ * so the futures do not depend on any particular async runtime.
+ * Calls are executed in the order they were made.
+ */
+pub struct AsyncConnection {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl AsyncConnection {
+    /*
+     * Start the worker thread and connect from it
+     */
+    pub fn connect(builder: ConnectionBuilder) -> impl Future<Output = Result<Self>> {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let (reply, result) = channel::<Result<()>>();
+        let spawned = thread::Builder::new()
+            .name(String::from("freetds-async"))
+            .spawn(move || {
+                let mut conn = match builder.connect() {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        reply.send(Err(e));
+                        return;
+                    }
+                };
+                reply.send(Ok(()));
+
+                /* Runs until the AsyncConnection is dropped */
+                while let Ok(job) = receiver.recv() {
+                    job(&mut conn);
+                }
+            });
+
+        async move {
+            spawned.map_err(|e| Error::from_message(format!("Cannot start worker thread: {}", e)))?;
+            result.await??;
+            Ok(Self { jobs })
+        }
+    }
+
+    /*
+     * Run a closure on the worker thread, with exclusive access to the connection
+     */
+    pub fn run<F, T>(&self, f: F) -> impl Future<Output = Result<T>>
+    where
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = channel::<Result<T>>();
+        let sent = self.jobs.send(Box::new(move |conn| reply.send(f(conn))));
+        async move {
+            if sent.is_err() {
+                return Err(Error::from_message("Connection worker thread stopped"));
+            }
+            result.await?
+        }
+    }
+
+    /*
+     * Like Connection::execute. All rows are read on the worker thread,
+     * even if the connection is in streaming mode.
+     */
+    pub fn execute(&self, text: impl Into<String>, params: Vec<Value>) -> impl Future<Output = Result<ResultSet>> {
+        let text = text.into();
+        self.run(move |conn| {
+            let params: Vec<&dyn ToSql> = params.iter().map(|param| param as &dyn ToSql).collect();
+            let streaming = conn.lock().streaming;
+            conn.set_streaming(false);
+            let result = conn.execute(&text, &params);
+            conn.set_streaming(streaming);
+            result
+        })
+    }
+
+    /*
+     * Execute a query and return the rows of its first row result, converted on the worker thread
+     */
+    pub fn fetch(&self, text: impl Into<String>, params: Vec<Value>) -> impl Future<Output = Result<Vec<Vec<Value>>>> {
+        let text = text.into();
+        self.run(move |conn| {
+            let params: Vec<&dyn ToSql> = params.iter().map(|param| param as &dyn ToSql).collect();
+            let mut rs = conn.execute(&text, &params)?;
+            let mut rows = Vec::new();
+            while rs.try_next()? {
+                let mut row = Vec::new();
+                for col in 0..rs.column_count()? {
+                    row.push(rs.get_value(col as i32)?);
+                }
+                rows.push(row);
+            }
+            Ok(rows)
+        })
+    }
+}
+
+/*
+ * Single-value channel whose receiving end is a future
+ */
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+struct Reply<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+struct ReplyFuture<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+fn channel<T>() -> (Reply<T>, ReplyFuture<T>) {
+    let slot = Arc::new(Mutex::new(Slot {
+        value: None,
+        waker: None,
+        closed: false,
+    }));
+    (Reply { slot: Arc::clone(&slot) }, ReplyFuture { slot })
+}
+
+impl<T> Reply<T> {
+    fn send(self, value: T) {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner).value = Some(value);
+    }
+}
+
+impl<T> Drop for Reply<T> {
+    fn drop(&mut self) {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        slot.closed = true;
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for ReplyFuture<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(value) = slot.value.take() {
+            Poll::Ready(Ok(value))
+        } else if slot.closed {
+            Poll::Ready(Err(Error::from_message("Connection worker thread stopped")))
+        } else {
+            slot.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel, AsyncConnection};
+    use crate::tests::builder;
+    use crate::Value;
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_reply() {
+        let (reply, result) = channel::<i32>();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            reply.send(42);
+        });
+        assert_eq!(42, block_on(result).unwrap());
+
+        let (reply, result) = channel::<i32>();
+        drop(reply);
+        assert!(block_on(result).is_err());
+    }
+
+    #[test]
+    fn test_async_connection() {
+        let conn = block_on(AsyncConnection::connect(builder())).unwrap();
+
+        let rows = block_on(conn.fetch("select ?, ?", vec![Value::from(42), Value::from("abc")])).unwrap();
+        assert_eq!(vec![vec![Value::from(42), Value::from("abc")]], rows);
+
+        let mut rs = block_on(conn.execute("select db_name()", vec![])).unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(String::from("master")), rs.get_string(0).unwrap());
+
+        let name = block_on(conn.run(|conn| conn.db_name())).unwrap();
+        assert_eq!("master", name);
+
+        assert!(block_on(conn.fetch("select * from no_such_table", vec![])).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::command::{Command, CommandArg};
+use crate::connection::ReadState;
+use crate::result_set::SybResult;
+use crate::to_sql::ToSql;
+use crate::{generate_query, parse_query, Connection, Error, Result};
+use freetds_sys::*;
+use std::io;
+
+/*
+ * Rows of a query whose columns are read as streams with ct_get_data,
+ * obtained with Connection::query_blobs
+ * Unlike ResultSet, text and image values are not limited by the size of a bound buffer.
+ * Columns of a row must be read in increasing order.
+ */
+pub struct BlobRows {
+    command: Command,
+    column_count: usize,
+
+    /* next column which can be read in the current row (1-based), None before the first row */
+    next_item: Option<i32>,
+    finished: bool,
+}
+
+impl BlobRows {
+    pub(crate) fn new(conn: &Connection, text: &str, params: &[&dyn ToSql]) -> Result<Self> {
+        let parsed_query = parse_query(text);
+        if parsed_query.params.len() != params.len() {
+            return Err(Error::from_message("Invalid parameter count"));
+        }
+        let mut conn = conn.share();
+        let server_side_params = conn.lock().server_side_params;
+        let mut command = if server_side_params {
+            let values = Connection::bound_values(params)?;
+            conn.send_bound(&parsed_query, &values)?
+        } else {
+            let mut text = String::new();
+            generate_query(&mut text, &parsed_query, params.iter().copied(), conn.dialect())
+                .map_err(|e| Error::from_message(e.to_string()))?;
+
+            let mut command = Command::new(conn.share());
+            command.command(CS_LANG_CMD, CommandArg::String(&text), CS_UNUSED)?;
+            command.send()?;
+            command
+        };
+
+        /* Rows are read with ct_get_data, which needs the columns to be left unbound */
+        let mut state = ReadState {
+            streaming: false,
+            unbound: true,
+            fail_on_status: true,
+            failed: false,
+            errors: Vec::new(),
+        };
+        let mut column_count = None;
+        while let Some((result, _)) = conn.read_result(&mut command, &mut state)? {
+            if let SybResult::Rows(rows) = result {
+                column_count = Some(rows.columns.len());
+                break;
+            }
+        }
+
+        match column_count {
+            Some(column_count) if !state.failed => Ok(Self {
+                command,
+                column_count,
+                next_item: None,
+                finished: false,
+            }),
+            _ => Err(Connection::get_single_error(&state.errors)
+                .unwrap_or_else(|| Error::from_message("Query did not return rows"))),
+        }
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    /*
+     * Move to the next row, returns false when all rows have been read
+     */
+    pub fn next_row(&mut self) -> Result<bool> {
+        if self.finished {
+            return Ok(false);
+        }
+        if self.command.fetch()? {
+            self.next_item = Some(1);
+            Ok(true)
+        } else {
+            self.finished = true;
+            self.next_item = None;
+            Ok(false)
+        }
+    }
+
+    /*
+     * Stream the value of a column (0-based) of the current row
+     * A null value reads as empty.
+     */
+    pub fn column(&mut self, index: usize) -> Result<BlobReader<'_>> {
+        let next_item = self
+            .next_item
+            .ok_or_else(|| Error::from_message("BlobRows does not currently point to a row"))?;
+        if index >= self.column_count {
+            return Err(Error::from_message("Invalid column index"));
+        }
+        let item = (index + 1) as i32;
+        if item < next_item {
+            return Err(Error::from_message("Columns must be read in increasing order"));
+        }
+        self.next_item = Some(item + 1);
+        Ok(BlobReader {
+            command: &mut self.command,
+            item,
+            started: false,
+            finished: false,
+        })
+    }
+}
+
+/*
+ * Reader over one column of the current row of BlobRows
+ */
+pub struct BlobReader<'a> {
+    command: &'a mut Command,
+    item: i32,
+    started: bool,
+    finished: bool,
+}
+
+impl BlobReader<'_> {
+    /*
+     * Text pointer of the column, used to write a new value with Connection::blob_writer
+     * The column must not be null.
+     */
+    pub fn text_pointer(&mut self) -> Result<TextPointer> {
+        if !self.started {
+            /* ct_data_info needs ct_get_data to have been called on the column */
+            self.read_chunk(&mut [])?;
+        }
+        let mut iodesc: CS_IODESC = Default::default();
+        self.command.data_info(CS_GET, self.item, &mut iodesc)?;
+        if iodesc.textptrlen == 0 {
+            return Err(Error::from_message("Column has no text pointer"));
+        }
+        Ok(TextPointer::from(&iodesc))
+    }
+
+    fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if self.finished {
+            return Ok(0);
+        }
+        self.started = true;
+        let (ret, len) = self.command.get_data(self.item, buffer)?;
+        if ret != CS_SUCCEED {
+            self.finished = true;
+        }
+        Ok(len)
+    }
+}
+
+impl io::Read for BlobReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.read_chunk(buf).map_err(io::Error::other)
+    }
+}
+
+/*
+ * Location of a text or image value on the server, see BlobReader::text_pointer
+ */
+#[derive(Clone)]
+pub struct TextPointer {
+    iotype: i32,
+    datatype: i32,
+    usertype: i32,
+    name: [CS_CHAR; 132],
+    namelen: i32,
+    timestamp: [CS_BYTE; 8],
+    timestamplen: i32,
+    textptr: [CS_BYTE; 16],
+    textptrlen: i32,
+}
+
+impl From<&CS_IODESC> for TextPointer {
+    fn from(iodesc: &CS_IODESC) -> Self {
+        Self {
+            iotype: iodesc.iotype,
+            datatype: iodesc.datatype,
+            usertype: iodesc.usertype,
+            name: iodesc.name,
+            namelen: iodesc.namelen,
+            timestamp: iodesc.timestamp,
+            timestamplen: iodesc.timestamplen,
+            textptr: iodesc.textptr,
+            textptrlen: iodesc.textptrlen,
+        }
+    }
+}
+
+impl TextPointer {
+    fn to_iodesc(&self) -> CS_IODESC {
+        CS_IODESC {
+            iotype: self.iotype,
+            datatype: self.datatype,
+            usertype: self.usertype,
+            name: self.name,
+            namelen: self.namelen,
+            timestamp: self.timestamp,
+            timestamplen: self.timestamplen,
+            textptr: self.textptr,
+            textptrlen: self.textptrlen,
+            ..Default::default()
+        }
+    }
+}
+
+/*
+ * Writer replacing a text or image value, obtained with Connection::blob_writer
+ * Exactly the length given when creating the writer must be written before calling finish.
+ * The value is left unchanged if the writer is dropped without calling finish.
+ */
+pub struct BlobWriter {
+    command: Command,
+    remaining: usize,
+}
+
+impl BlobWriter {
+    pub(crate) fn new(conn: &Connection, pointer: &TextPointer, len: usize) -> Result<Self> {
+        let mut command = Command::new(conn.share());
+        command.command(CS_SEND_DATA_CMD, CommandArg::Unused, CS_COLUMN_DATA)?;
+
+        let mut iodesc = pointer.to_iodesc();
+        iodesc.total_txtlen = len as i32;
+        iodesc.log_on_update = CS_TRUE;
+        command.data_info(CS_SET, CS_UNUSED, &mut iodesc)?;
+        Ok(Self { command, remaining: len })
+    }
+
+    /*
+     * Send the value to the server
+     */
+    pub fn finish(mut self) -> Result<()> {
+        if self.remaining != 0 {
+            return Err(Error::from_message(format!("{} bytes remain to be written", self.remaining)));
+        }
+        self.command.send()?;
+
+        /* The only result is the new timestamp of the row */
+        let mut conn = self.command.conn.share();
+        let mut state = ReadState {
+            streaming: false,
+            unbound: false,
+            fail_on_status: true,
+            failed: false,
+            errors: Vec::new(),
+        };
+        while conn.read_result(&mut self.command, &mut state)?.is_some() {}
+
+        if state.failed {
+            return Err(Connection::get_single_error(&state.errors)
+                .unwrap_or_else(|| Error::from_message("Sending data failed")));
+        }
+        Ok(())
+    }
+}
+
+impl io::Write for BlobWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.remaining {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Data exceeds the declared length"));
+        }
+        self.command
+            .send_data(buf)
+            .map_err(io::Error::other)?;
+        self.remaining -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{connect, scratch_table};
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_blob() {
+        let mut conn = connect();
+        scratch_table(&mut conn, "freetds_blob", "id int, data image null");
+        conn.execute("insert into freetds_blob values(1, 0x00)", &[]).unwrap();
+
+        let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+        let pointer = {
+            let mut rows = conn.query_blobs("select id, data from freetds_blob where id = ?", &[&1]).unwrap();
+            assert!(rows.next_row().unwrap());
+            let mut id = Vec::new();
+            rows.column(0).unwrap().read_to_end(&mut id).unwrap();
+            assert_eq!(1i32.to_ne_bytes().to_vec(), id);
+            let pointer = rows.column(1).unwrap().text_pointer().unwrap();
+            assert!(rows.column(0).is_err());
+            pointer
+        };
+
+        let mut writer = conn.blob_writer(&pointer, data.len()).unwrap();
+        for chunk in data.chunks(4096) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert!(writer.write(&[0]).is_err());
+        writer.finish().unwrap();
+
+        conn.execute("set textsize 1000000", &[]).unwrap();
+        let mut rows = conn.query_blobs("select data from freetds_blob", &[]).unwrap();
+        assert!(rows.next_row().unwrap());
+        let mut read = Vec::new();
+        rows.column(0).unwrap().read_to_end(&mut read).unwrap();
+        assert_eq!(data, read);
+        assert!(!rows.next_row().unwrap());
+
+        /* Parameters bound with ct_param */
+        conn.set_server_side_params(true);
+        let mut rows = conn.query_blobs("select id from freetds_blob where id = ?", &[&1]).unwrap();
+        assert!(rows.next_row().unwrap());
+        let mut id = Vec::new();
+        rows.column(0).unwrap().read_to_end(&mut id).unwrap();
+        assert_eq!(1i32.to_ne_bytes().to_vec(), id);
+        assert!(!rows.next_row().unwrap());
+    }
+}
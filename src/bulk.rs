@@ -0,0 +1,407 @@
+use crate::command::{Command, CommandArg};
+use crate::connection::ReadState;
+use crate::handle::HandleId;
+use crate::param::Param;
+use crate::result_set::{buffer_to_value, SybResult};
+use crate::util::quote_object_name;
+use crate::{Connection, Error, Result, Value};
+use freetds_sys::*;
+use log::warn;
+use std::ffi::CString;
+use std::ptr;
+
+/*
+ * RAII for the bulk copy descriptor
+ */
+struct BlkDesc {
+    conn: Connection,
+    id: HandleId,
+    column_count: usize,
+}
+
+impl BlkDesc {
+    /*
+     * Allocate the descriptor and start copying in the given direction (CS_BLK_IN/CS_BLK_OUT)
+     */
+    fn new(conn: &Connection, table: &str, direction: i32) -> Result<Self> {
+        let column_count = Self::column_count(conn, table)?;
+
+        let mut conn = conn.share();
+        conn.diag_clear();
+        let mut handle: *mut CS_BLKDESC = ptr::null_mut();
+        let ret = unsafe { blk_alloc(conn.lock().conn_handle(), BLK_VERSION_100, &mut handle) };
+        if ret != CS_SUCCEED {
+            return Err(conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_failure("blk_alloc")));
+        }
+        let id = conn.lock().add_blkdesc(handle);
+        let mut blk = Self {
+            conn,
+            id,
+            column_count,
+        };
+
+        let table = CString::new(table)?;
+        blk.check("blk_init", |handle| unsafe {
+            blk_init(handle, direction, table.as_ptr() as *mut CS_CHAR, CS_NULLTERM)
+        })?;
+        Ok(blk)
+    }
+
+    /*
+     * Number of columns of the table, read from the metadata of an empty query on it
+     * This must be done before blk_init, the connection is busy with the bulk copy afterwards.
+     */
+    fn column_count(conn: &Connection, table: &str) -> Result<usize> {
+        let mut conn = conn.share();
+        let text = format!("select * from {} where 1=0", quote_object_name(table));
+        let mut command = Command::new(conn.share());
+        command.command(CS_LANG_CMD, CommandArg::String(&text), CS_UNUSED)?;
+        command.send()?;
+
+        let mut state = ReadState {
+            streaming: false,
+            unbound: true,
+            fail_on_status: true,
+            failed: false,
+            errors: Vec::new(),
+        };
+        let mut column_count = None;
+        while let Some((result, _)) = conn.read_result(&mut command, &mut state)? {
+            if let SybResult::Rows(rows) = result {
+                /* dropping the command cancels the remaining results */
+                column_count = Some(rows.columns.len());
+                break;
+            }
+        }
+
+        match column_count {
+            Some(column_count) if !state.failed => Ok(column_count),
+            _ => Err(Connection::get_single_error(&state.errors)
+                .unwrap_or_else(|| Error::from_message("Cannot get the table's columns"))),
+        }
+    }
+
+    /*
+     * Run a blk_* function under the connection lock, and turn its return code into a Result
+     */
+    fn check(&mut self, fn_name: &str, f: impl FnOnce(*mut CS_BLKDESC) -> CS_RETCODE) -> Result<()> {
+        self.conn.diag_clear();
+        let ret = {
+            let guard = self.conn.lock();
+            f(guard.blkdesc(self.id))
+        };
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_failure(fn_name)))
+        }
+    }
+
+    /*
+     * Describe the table's columns
+     */
+    fn describe(&mut self) -> Result<Vec<CS_DATAFMT>> {
+        let mut columns = Vec::with_capacity(self.column_count);
+        for item in 1..=self.column_count as i32 {
+            let mut fmt: CS_DATAFMT = Default::default();
+            self.check("blk_describe", |handle| unsafe { blk_describe(handle, item, &mut fmt) })?;
+            fmt.locale = ptr::null_mut();
+            columns.push(fmt);
+        }
+        Ok(columns)
+    }
+
+    fn done(&mut self, type_: i32) -> Result<u64> {
+        let mut rows: i32 = 0;
+        self.check("blk_done", |handle| unsafe { blk_done(handle, type_, &mut rows) })?;
+        Ok(rows as u64)
+    }
+}
+
+impl Drop for BlkDesc {
+    fn drop(&mut self) {
+        let mut guard = self.conn.lock();
+        unsafe {
+            let ret = blk_drop(guard.remove_blkdesc(self.id));
+            if ret != CS_SUCCEED {
+                warn!("blk_drop failed");
+            }
+        }
+    }
+}
+
+/*
+ * Bulk copy of rows into a table, obtained with Connection::bulk_insert
+ * The connection must have been opened with ConnectionBuilder::bulk_login.
+ * Rows which were not committed by a batch or by finish are discarded on drop.
+ */
+pub struct BulkInsert {
+    blk: BlkDesc,
+    column_count: usize,
+    batch_size: Option<usize>,
+
+    /* rows sent since the last batch */
+    pending: usize,
+
+    /* rows committed */
+    copied: u64,
+    finished: bool,
+}
+
+impl BulkInsert {
+    pub(crate) fn new(conn: &Connection, table: &str) -> Result<Self> {
+        let mut blk = BlkDesc::new(conn, table, CS_BLK_IN)?;
+        let column_count = blk.describe()?.len();
+        Ok(Self {
+            blk,
+            column_count,
+            batch_size: None,
+            pending: 0,
+            copied: 0,
+            finished: false,
+        })
+    }
+
+    /*
+     * Commit automatically every batch_size rows
+     */
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    /*
+     * Send one row, with a value for each column of the table, in table order
+     * Values are converted to the column types by the library.
+     */
+    pub fn send_row(&mut self, row: &[Value]) -> Result<()> {
+        if row.len() != self.column_count {
+            return Err(Error::from_message(format!(
+                "Invalid value count: expected {}, got {}",
+                self.column_count,
+                row.len()
+            )));
+        }
+
+        /* The buffers must stay alive until blk_rowxfer */
+        let mut params = row
+            .iter()
+            .map(|value| Param::new(&mut self.blk.conn, value))
+            .collect::<Result<Vec<Param>>>()?;
+        let mut lengths: Vec<i32> = params.iter().map(|param| param.buffer.len() as i32).collect();
+
+        for (i, (param, length)) in params.iter_mut().zip(lengths.iter_mut()).enumerate() {
+            self.blk.check("blk_bind", |handle| unsafe {
+                blk_bind(
+                    handle,
+                    (i + 1) as i32,
+                    &mut param.fmt,
+                    param.buffer.as_mut_ptr() as *mut CS_VOID,
+                    length,
+                    &mut param.indicator,
+                )
+            })?;
+        }
+        self.blk.check("blk_rowxfer", |handle| unsafe { blk_rowxfer(handle) })?;
+
+        self.pending += 1;
+        if let Some(batch_size) = self.batch_size {
+            if self.pending >= batch_size {
+                self.batch()?;
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Commit the rows sent so far, returns the number of rows committed by this batch
+     */
+    pub fn batch(&mut self) -> Result<u64> {
+        let rows = self.blk.done(CS_BLK_BATCH)?;
+        self.pending = 0;
+        self.copied += rows;
+        Ok(rows)
+    }
+
+    /*
+     * Rows committed so far
+     */
+    pub fn rows_copied(&self) -> u64 {
+        self.copied
+    }
+
+    /*
+     * Commit the remaining rows and end the bulk copy, returns the total number of rows copied
+     */
+    pub fn finish(mut self) -> Result<u64> {
+        self.finished = true;
+        let rows = self.blk.done(CS_BLK_ALL)?;
+        self.copied += rows;
+        Ok(self.copied)
+    }
+}
+
+impl Drop for BulkInsert {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.blk.done(CS_BLK_CANCEL) {
+                warn!("Cannot cancel the bulk copy: {}", e);
+            }
+        }
+    }
+}
+
+/*
+ * Bulk copy all rows of a table out, see Connection::bulk_out
+ */
+pub(crate) fn bulk_out(conn: &Connection, table: &str) -> Result<Vec<Vec<Value>>> {
+    let text_size = text_size(conn)?;
+    let mut blk = BlkDesc::new(conn, table, CS_BLK_OUT)?;
+    let mut columns = blk.describe()?;
+
+    let mut buffers: Vec<Vec<u8>> = Vec::with_capacity(columns.len());
+    let mut lengths: Vec<i32> = vec![0; columns.len()];
+    let mut indicators: Vec<i16> = vec![0; columns.len()];
+    for fmt in columns.iter_mut() {
+        /* Text and image columns are described with a maxlength of about 2GB */
+        fmt.maxlength = fmt.maxlength.min(text_size);
+        fmt.format = CS_FMT_UNUSED as i32;
+        if is_char_type(fmt.datatype) {
+            fmt.maxlength += 1;
+            fmt.format = CS_FMT_NULLTERM as i32;
+        }
+        fmt.count = 1;
+        buffers.push(vec![0u8; fmt.maxlength as usize]);
+    }
+
+    for (i, fmt) in columns.iter_mut().enumerate() {
+        let buffer = buffers[i].as_mut_ptr() as *mut CS_VOID;
+        let length: *mut i32 = &mut lengths[i];
+        let indicator: *mut i16 = &mut indicators[i];
+        blk.check("blk_bind", |handle| unsafe {
+            blk_bind(handle, (i + 1) as i32, fmt, buffer, length, indicator)
+        })?;
+    }
+
+    let mut rows = Vec::new();
+    loop {
+        blk.conn.diag_clear();
+        let ret = {
+            let guard = blk.conn.lock();
+            unsafe { blk_rowxfer(guard.blkdesc(blk.id)) }
+        };
+        match ret {
+            CS_END_DATA => break,
+            CS_SUCCEED => {},
+            _ => {
+                return Err(blk
+                    .conn
+                    .get_error()
+                    .unwrap_or_else(|| Error::from_failure("blk_rowxfer")));
+            },
+        }
+
+        let mut row = Vec::with_capacity(columns.len());
+        for (i, fmt) in columns.iter().enumerate() {
+            let value = match indicators[i] {
+                -1 => Value::Null,
+                0 => {
+                    let mut len = lengths[i] as usize;
+                    if is_char_type(fmt.datatype) && len > 0 {
+                        len -= 1;
+                    }
+                    buffer_to_value(&mut blk.conn, fmt, &buffers[i][0..len])?
+                },
+                _ => {
+                    return Err(Error::from_message(format!(
+                        "Data truncation occured in column {}: values longer than @@textsize ({}) cannot be copied out",
+                        i + 1,
+                        text_size
+                    )))
+                },
+            };
+            row.push(value);
+        }
+        rows.push(row);
+    }
+    blk.done(CS_BLK_ALL)?;
+    Ok(rows)
+}
+
+/*
+ * Largest text or image value copied out, the session's @@textsize
+ * This must be read before blk_init, like BlkDesc::column_count.
+ */
+fn text_size(conn: &Connection) -> Result<i32> {
+    const DEFAULT_TEXT_SIZE: i32 = 32768;
+
+    let mut conn = conn.share();
+    let mut rs = conn.execute("select @@textsize", &[])?;
+    if !rs.next() {
+        return Err(Error::from_message("Cannot get @@textsize"));
+    }
+    match rs.get_i32(0)? {
+        Some(size) if size > 0 => Ok(size.min(i32::MAX - 1)),
+        _ => Ok(DEFAULT_TEXT_SIZE),
+    }
+}
+
+fn is_char_type(datatype: i32) -> bool {
+    matches!(
+        datatype,
+        CS_CHAR_TYPE | CS_LONGCHAR_TYPE | CS_VARCHAR_TYPE | CS_UNICHAR_TYPE | CS_TEXT_TYPE | CS_UNITEXT_TYPE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{builder, scratch_table};
+    use crate::Value;
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<super::BulkInsert>();
+    }
+
+    #[test]
+    fn test_bulk_copy() {
+        let mut conn = builder().bulk_login(true).connect().unwrap();
+        scratch_table(&mut conn, "freetds_bulk", "id int, name varchar(32) null, amount numeric(10,2) null");
+
+        let mut bulk = conn.bulk_insert("freetds_bulk").unwrap().batch_size(2);
+        assert_eq!(3, bulk.column_count());
+        for i in 0..5 {
+            bulk.send_row(&[Value::from(i), Value::from(format!("row {}", i)), Value::Null]).unwrap();
+        }
+        assert_eq!(4, bulk.rows_copied());
+        assert!(bulk.send_row(&[Value::from(5)]).is_err());
+        assert_eq!(5, bulk.finish().unwrap());
+
+        let rows = conn.bulk_out("freetds_bulk").unwrap();
+        assert_eq!(5, rows.len());
+        assert_eq!(vec![Value::from(0), Value::from("row 0"), Value::Null], rows[0]);
+    }
+
+    #[test]
+    fn test_bulk_out_text() {
+        let mut conn = builder().bulk_login(true).connect().unwrap();
+        scratch_table(&mut conn, "freetds_bulk_text", "id int, body text null");
+        conn.execute("insert into freetds_bulk_text values(1, replicate('x', 1000))", &[]).unwrap();
+        conn.execute("insert into freetds_bulk_text values(2, null)", &[]).unwrap();
+
+        let rows = conn.bulk_out("freetds_bulk_text").unwrap();
+        assert_eq!(2, rows.len());
+        assert_eq!(vec![Value::from(1), Value::from("x".repeat(1000))], rows[0]);
+        assert_eq!(vec![Value::from(2), Value::Null], rows[1]);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::connection::CSConnection;
+use crate::{Connection, Error, Result};
+use freetds_sys::*;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+/*
+ * The connection cancelled by the CancelHandles of a Connection
+ */
+pub(crate) struct CancelTarget {
+    /* Does not keep the connection open */
+    conn: Weak<Mutex<CSConnection>>,
+
+    /*
+     * The connection handle, which cannot be read from the CSConnection
+     * while the call to interrupt holds its lock
+     */
+    handle: AtomicPtr<CS_CONNECTION>,
+}
+
+impl CancelTarget {
+    pub fn new(conn: Weak<Mutex<CSConnection>>, handle: *mut CS_CONNECTION) -> Self {
+        Self {
+            conn,
+            handle: AtomicPtr::new(handle),
+        }
+    }
+
+    /*
+     * Point the CancelHandles at another connection, after Connection::reconnect
+     */
+    pub fn retarget(&mut self, conn: &Arc<Mutex<CSConnection>>, handle: *mut CS_CONNECTION) {
+        self.conn = Arc::downgrade(conn);
+        self.handle.store(handle, Ordering::Release);
+    }
+}
+
+/*
+ * Cancels the query running on a connection, from any thread
+ * Obtained with Connection::cancel_handle or ResultSet::cancel_handle.
+ */
+#[derive(Clone)]
+pub struct CancelHandle {
+    target: Arc<Mutex<CancelTarget>>,
+}
+
+impl CancelHandle {
+    pub(crate) fn new(conn: &Connection) -> Self {
+        Self {
+            target: Arc::clone(&conn.lock().cancel_target),
+        }
+    }
+
+    /*
+     * Send an attention to the server: the blocked call returns an error
+     * for which Error::is_cancelled is true.
+     * Does nothing if the connection was closed.
+     */
+    pub fn cancel(&self) -> Result<()> {
+        let target = self.target.lock().unwrap_or_else(PoisonError::into_inner);
+
+        /* Keeps the connection handle allocated until ct_cancel returns */
+        let _conn = match target.conn.upgrade() {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        let handle = target.handle.load(Ordering::Acquire);
+
+        /*
+         * Called without the connection lock, which the interrupted call holds.
+         * CS_CANCEL_ATTN is the one ct_cancel mode meant to be used while another call
+         * is in progress on the connection (from a signal handler or another thread):
+         * FreeTDS only sends the attention packet, using its own socket lock, and leaves
+         * reading the acknowledgement to the interrupted call.
+         */
+        let ret = unsafe { ct_cancel(handle, ptr::null_mut(), CS_CANCEL_ATTN) };
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(Error::from_failure("ct_cancel"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancelHandle;
+    use crate::tests::connect;
+    use crate::Connection;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn cancel_waitfor(conn: &mut Connection, handle: CancelHandle) {
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            handle.cancel().unwrap();
+        });
+
+        let start = Instant::now();
+        let result = conn.execute("waitfor delay '00:00:10'", &[]);
+        t.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(result.err().unwrap().is_cancelled());
+
+        /* The connection is still usable */
+        let mut rs = conn.execute("select 1", &[]).unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(1), rs.get_i32(0).unwrap());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut conn = connect();
+        let handle = conn.cancel_handle();
+        cancel_waitfor(&mut conn, handle);
+    }
+
+    #[test]
+    fn test_cancel_reconnect() {
+        let mut conn = connect();
+        let handle = conn.cancel_handle();
+
+        /* The handle follows the connection to its new server connection */
+        conn.reconnect().unwrap();
+        cancel_waitfor(&mut conn, handle);
+    }
+
+    #[test]
+    fn test_cancel_closed() {
+        let conn = connect();
+        let handle = conn.cancel_handle();
+        drop(conn);
+
+        /* The handle does not keep the connection alive, and does nothing once it is closed */
+        handle.cancel().unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use crate::result_set::Column;
+use freetds_sys::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Char,
+    VarChar,
+    LongChar,
+    UniChar,
+    Text,
+    UniText,
+    Binary,
+    VarBinary,
+    LongBinary,
+    Image,
+    Bit,
+    TinyInt,
+    SmallInt,
+    Int,
+    BigInt,
+    USmallInt,
+    UInt,
+    UBigInt,
+    Real,
+    Float,
+    Money,
+    Money4,
+    Numeric,
+    Decimal,
+    DateTime,
+    DateTime4,
+    Date,
+    Time,
+    BigDateTime,
+    BigTime,
+    Xml,
+    Unique,
+
+    /* Any other CS_*_TYPE value */
+    Other(i32),
+}
+
+impl From<i32> for DataType {
+    fn from(datatype: i32) -> Self {
+        match datatype {
+            CS_CHAR_TYPE => Self::Char,
+            CS_VARCHAR_TYPE => Self::VarChar,
+            CS_LONGCHAR_TYPE => Self::LongChar,
+            CS_UNICHAR_TYPE => Self::UniChar,
+            CS_TEXT_TYPE => Self::Text,
+            CS_UNITEXT_TYPE => Self::UniText,
+            CS_BINARY_TYPE => Self::Binary,
+            CS_VARBINARY_TYPE => Self::VarBinary,
+            CS_LONGBINARY_TYPE => Self::LongBinary,
+            CS_IMAGE_TYPE => Self::Image,
+            CS_BIT_TYPE => Self::Bit,
+            CS_TINYINT_TYPE => Self::TinyInt,
+            CS_SMALLINT_TYPE => Self::SmallInt,
+            CS_INT_TYPE => Self::Int,
+            CS_BIGINT_TYPE => Self::BigInt,
+            CS_USMALLINT_TYPE => Self::USmallInt,
+            CS_UINT_TYPE => Self::UInt,
+            CS_UBIGINT_TYPE => Self::UBigInt,
+            CS_REAL_TYPE => Self::Real,
+            CS_FLOAT_TYPE => Self::Float,
+            CS_MONEY_TYPE => Self::Money,
+            CS_MONEY4_TYPE => Self::Money4,
+            CS_NUMERIC_TYPE => Self::Numeric,
+            CS_DECIMAL_TYPE => Self::Decimal,
+            CS_DATETIME_TYPE => Self::DateTime,
+            CS_DATETIME4_TYPE => Self::DateTime4,
+            CS_DATE_TYPE => Self::Date,
+            CS_TIME_TYPE => Self::Time,
+            CS_BIGDATETIME_TYPE => Self::BigDateTime,
+            CS_BIGTIME_TYPE => Self::BigTime,
+            CS_XML_TYPE => Self::Xml,
+            CS_UNIQUE_TYPE => Self::Unique,
+            _ => Self::Other(datatype),
+        }
+    }
+}
+
+impl DataType {
+    /*
+     * The CS_*_TYPE value of this type
+     */
+    pub(crate) fn cs_type(&self) -> i32 {
+        match self {
+            Self::Char => CS_CHAR_TYPE,
+            Self::VarChar => CS_VARCHAR_TYPE,
+            Self::LongChar => CS_LONGCHAR_TYPE,
+            Self::UniChar => CS_UNICHAR_TYPE,
+            Self::Text => CS_TEXT_TYPE,
+            Self::UniText => CS_UNITEXT_TYPE,
+            Self::Binary => CS_BINARY_TYPE,
+            Self::VarBinary => CS_VARBINARY_TYPE,
+            Self::LongBinary => CS_LONGBINARY_TYPE,
+            Self::Image => CS_IMAGE_TYPE,
+            Self::Bit => CS_BIT_TYPE,
+            Self::TinyInt => CS_TINYINT_TYPE,
+            Self::SmallInt => CS_SMALLINT_TYPE,
+            Self::Int => CS_INT_TYPE,
+            Self::BigInt => CS_BIGINT_TYPE,
+            Self::USmallInt => CS_USMALLINT_TYPE,
+            Self::UInt => CS_UINT_TYPE,
+            Self::UBigInt => CS_UBIGINT_TYPE,
+            Self::Real => CS_REAL_TYPE,
+            Self::Float => CS_FLOAT_TYPE,
+            Self::Money => CS_MONEY_TYPE,
+            Self::Money4 => CS_MONEY4_TYPE,
+            Self::Numeric => CS_NUMERIC_TYPE,
+            Self::Decimal => CS_DECIMAL_TYPE,
+            Self::DateTime => CS_DATETIME_TYPE,
+            Self::DateTime4 => CS_DATETIME4_TYPE,
+            Self::Date => CS_DATE_TYPE,
+            Self::Time => CS_TIME_TYPE,
+            Self::BigDateTime => CS_BIGDATETIME_TYPE,
+            Self::BigTime => CS_BIGTIME_TYPE,
+            Self::Xml => CS_XML_TYPE,
+            Self::Unique => CS_UNIQUE_TYPE,
+            Self::Other(datatype) => *datatype,
+        }
+    }
+}
+
+/*
+ * Description of a result column, as returned by ct_describe
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    name: String,
+    datatype: DataType,
+    max_length: i32,
+    precision: i32,
+    scale: i32,
+    status: i32,
+    usertype: i32,
+}
+
+impl ColumnInfo {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn datatype(&self) -> DataType {
+        self.datatype
+    }
+
+    /*
+     * Maximum length in bytes of the column's values
+     */
+    pub fn max_length(&self) -> i32 {
+        self.max_length
+    }
+
+    pub fn precision(&self) -> i32 {
+        self.precision
+    }
+
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.status & CS_CANBENULL != 0
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.status & CS_IDENTITY != 0
+    }
+
+    pub fn is_key(&self) -> bool {
+        self.status & CS_KEY != 0
+    }
+
+    pub fn is_timestamp(&self) -> bool {
+        self.status & CS_TIMESTAMP != 0
+    }
+
+    pub fn is_updatable(&self) -> bool {
+        self.status & CS_UPDATABLE != 0
+    }
+
+    /*
+     * Server user-defined type id
+     */
+    pub fn usertype(&self) -> i32 {
+        self.usertype
+    }
+}
+
+impl From<&Column> for ColumnInfo {
+    fn from(column: &Column) -> Self {
+        let fmt = &column.described;
+        Self {
+            name: column.name.clone(),
+            datatype: DataType::from(fmt.datatype),
+            max_length: fmt.maxlength,
+            precision: fmt.precision,
+            scale: fmt.scale,
+            status: fmt.status,
+            usertype: fmt.usertype,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColumnInfo, DataType};
+    use crate::result_set::Column;
+    use freetds_sys::*;
+
+    #[test]
+    fn test_column_info() {
+        let mut column = Column {
+            name: String::from("amount"),
+            ..Default::default()
+        };
+        column.described.datatype = CS_NUMERIC_TYPE;
+        column.described.maxlength = 35;
+        column.described.precision = 10;
+        column.described.scale = 2;
+        column.described.status = CS_CANBENULL | CS_KEY;
+        column.fmt = column.described;
+        column.fmt.maxlength += 1;
+
+        let info = ColumnInfo::from(&column);
+        assert_eq!("amount", info.name());
+        assert_eq!(DataType::Numeric, info.datatype());
+        assert_eq!(35, info.max_length());
+        assert_eq!(10, info.precision());
+        assert_eq!(2, info.scale());
+        assert!(info.is_nullable());
+        assert!(info.is_key());
+        assert!(!info.is_identity());
+        assert!(!info.is_timestamp());
+        assert_eq!(DataType::Other(1000), DataType::from(1000));
+        assert_eq!(CS_NUMERIC_TYPE, DataType::Numeric.cs_type());
+        assert_eq!(DataType::Money4, DataType::from(DataType::Money4.cs_type()));
+    }
+}
@@ -1,37 +1,47 @@
 #![allow(clippy::useless_transmute)]
 
-use crate::connection::{CSConnection, Connection};
+use crate::connection::Connection;
 use crate::error::Error;
+use crate::handle::HandleId;
+use crate::param::Param;
 use crate::Result;
 use freetds_sys::*;
+use log::warn;
 use std::ffi::CString;
 use std::mem;
 use std::ptr;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub(crate) struct CSCommand {
-    handle: *mut CS_COMMAND,
+    id: HandleId,
+
+    /* Keeps the connection alive until the command handle is dropped */
+    conn: Connection,
 }
 
 impl CSCommand {
-    pub fn new(conn: &CSConnection) -> Self {
+    pub fn new(conn: &Connection) -> Self {
         unsafe {
+            let mut guard = conn.lock();
             let mut cmd: *mut CS_COMMAND = ptr::null_mut();
-            let ret = ct_cmd_alloc(conn.conn_handle, &mut cmd);
+            let ret = ct_cmd_alloc(guard.conn_handle(), &mut cmd);
             if ret != CS_SUCCEED {
                 panic!("ct_cmd_alloc failed");
             }
-            Self { handle: cmd }
+            let id = guard.add_command(cmd);
+            drop(guard);
+            Self { id, conn: conn.share() }
         }
     }
 }
 
 impl Drop for CSCommand {
     fn drop(&mut self) {
+        let mut guard = self.conn.lock();
         unsafe {
-            let ret = ct_cmd_drop(self.handle);
+            let ret = ct_cmd_drop(guard.remove_command(self.id));
             if ret != CS_SUCCEED {
-                panic!("ct_cmd_drop failed");
+                warn!("ct_cmd_drop failed");
             }
         }
     }
@@ -39,17 +49,20 @@ impl Drop for CSCommand {
 
 pub enum CommandArg<'a> {
     String(&'a str),
+
+    /* No buffer, for CS_SEND_DATA_CMD */
+    Unused,
 }
 
 #[derive(Clone)]
 pub(crate) struct Command {
     pub conn: Connection,
-    pub cmd: Rc<CSCommand>,
+    pub cmd: Arc<CSCommand>,
 }
 
 impl Command {
     pub fn new(conn: Connection) -> Self {
-        let cmd = Rc::new(CSCommand::new(&conn.conn.borrow()));
+        let cmd = Arc::new(CSCommand::new(&conn));
         Self { conn, cmd }
     }
 
@@ -57,14 +70,15 @@ impl Command {
         unsafe {
             match buffer {
                 CommandArg::String(s) => {
-                    assert_eq!(cmd_type, CS_LANG_CMD);
+                    assert!(cmd_type == CS_LANG_CMD || cmd_type == CS_RPC_CMD);
                     self.conn.diag_clear();
 
                     let buffer = CString::new(s)?;
                     let ret;
                     {
+                        let guard = self.conn.lock();
                         ret = ct_command(
-                            self.cmd.handle,
+                            guard.command(self.cmd.id),
                             cmd_type,
                             mem::transmute(buffer.as_ptr()),
                             CS_NULLTERM,
@@ -78,16 +92,114 @@ impl Command {
                             .unwrap_or_else(|| Error::from_message("ct_command failed")));
                     }
                 }
+                CommandArg::Unused => {
+                    assert!(cmd_type == CS_SEND_DATA_CMD);
+                    self.conn.diag_clear();
+
+                    let ret;
+                    {
+                        let guard = self.conn.lock();
+                        ret = ct_command(guard.command(self.cmd.id), cmd_type, ptr::null_mut(), CS_UNUSED, option);
+                    }
+                    if ret != CS_SUCCEED {
+                        return Err(self
+                            .conn
+                            .get_error()
+                            .unwrap_or_else(|| Error::from_message("ct_command failed")));
+                    }
+                }
             }
         }
         Ok(())
     }
 
+    /*
+     * Set up a cursor command, name and text are passed as CS_UNUSED when None
+     */
+    pub fn cursor(&mut self, type_: i32, name: Option<&str>, text: Option<&str>, option: i32) -> Result<()> {
+        self.conn.diag_clear();
+        let name = name.map(CString::new).transpose()?;
+        let text = text.map(CString::new).transpose()?;
+        let (name_ptr, name_len) = match &name {
+            Some(name) => (name.as_ptr() as *mut CS_CHAR, CS_NULLTERM),
+            None => (ptr::null_mut(), CS_UNUSED),
+        };
+        let (text_ptr, text_len) = match &text {
+            Some(text) => (text.as_ptr() as *mut CS_CHAR, CS_NULLTERM),
+            None => (ptr::null_mut(), CS_UNUSED),
+        };
+
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_cursor(guard.command(self.cmd.id), type_, name_ptr, name_len, text_ptr, text_len, option);
+        }
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_cursor failed")))
+        }
+    }
+
+    /*
+     * Set up a dynamic SQL command, text is passed as CS_UNUSED when None
+     */
+    pub fn dynamic(&mut self, type_: i32, id: &str, text: Option<&str>) -> Result<()> {
+        self.conn.diag_clear();
+        let id = CString::new(id)?;
+        let text = text.map(CString::new).transpose()?;
+        let (text_ptr, text_len) = match &text {
+            Some(text) => (text.as_ptr() as *mut CS_CHAR, CS_NULLTERM),
+            None => (ptr::null_mut(), CS_UNUSED),
+        };
+
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_dynamic(guard.command(self.cmd.id), type_, id.as_ptr() as *mut CS_CHAR, CS_NULLTERM, text_ptr, text_len);
+        }
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_dynamic failed")))
+        }
+    }
+
+    pub fn param(&mut self, param: &mut Param) -> Result<()> {
+        self.conn.diag_clear();
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_param(
+                guard.command(self.cmd.id),
+                &mut param.fmt,
+                param.buffer.as_mut_ptr() as *mut CS_VOID,
+                param.buffer.len() as i32,
+                param.indicator,
+            );
+        }
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_param failed")))
+        }
+    }
+
     pub fn send(&mut self) -> Result<()> {
         self.conn.diag_clear();
         let ret;
         unsafe {
-            ret = ct_send(self.cmd.handle);
+            let guard = self.conn.lock();
+            ret = ct_send(guard.command(self.cmd.id));
         }
         if ret == CS_SUCCEED {
             Ok(())
@@ -104,9 +216,12 @@ impl Command {
         let mut result_type: i32 = Default::default();
         let ret;
         unsafe {
-            ret = ct_results(self.cmd.handle, &mut result_type);
+            let guard = self.conn.lock();
+            ret = ct_results(guard.command(self.cmd.id), &mut result_type);
         }
-        if ret != CS_SUCCEED && ret != CS_END_RESULTS {
+        if ret == CS_CANCELED {
+            Err(Error::cancelled())
+        } else if ret != CS_SUCCEED && ret != CS_END_RESULTS {
             Err(self
                 .conn
                 .get_error()
@@ -127,7 +242,8 @@ impl Command {
         self.conn.diag_clear();
         let ret;
         {
-            ret = ct_bind(self.cmd.handle, item, datafmt, buffer, data_length, indicator);
+            let guard = self.conn.lock();
+            ret = ct_bind(guard.command(self.cmd.id), item, datafmt, buffer, data_length, indicator);
         }
         if ret == CS_SUCCEED {
             Ok(())
@@ -144,12 +260,15 @@ impl Command {
         let mut rows_read: i32 = Default::default();
         let ret;
         unsafe {
-            ret = ct_fetch(self.cmd.handle, CS_UNUSED, CS_UNUSED, CS_UNUSED, &mut rows_read);
+            let guard = self.conn.lock();
+            ret = ct_fetch(guard.command(self.cmd.id), CS_UNUSED, CS_UNUSED, CS_UNUSED, &mut rows_read);
         }
         if ret == CS_SUCCEED {
             Ok(true)
         } else if ret == CS_END_DATA {
             Ok(false)
+        } else if ret == CS_CANCELED {
+            Err(Error::cancelled())
         } else {
             Err(self
                 .conn
@@ -158,6 +277,69 @@ impl Command {
         }
     }
 
+    /*
+     * Read the next chunk of an unbound column of the current row
+     * Returns the ct_get_data return code (CS_SUCCEED, CS_END_ITEM or CS_END_DATA)
+     * and the number of bytes read.
+     */
+    pub fn get_data(&mut self, item: i32, buffer: &mut [u8]) -> Result<(i32, usize)> {
+        self.conn.diag_clear();
+        let mut out_len: i32 = 0;
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_get_data(
+                guard.command(self.cmd.id),
+                item,
+                buffer.as_mut_ptr() as *mut CS_VOID,
+                buffer.len() as i32,
+                &mut out_len,
+            );
+        }
+        match ret {
+            CS_SUCCEED | CS_END_ITEM | CS_END_DATA => Ok((ret, out_len as usize)),
+            CS_CANCELED => Err(Error::cancelled()),
+            _ => Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_get_data failed"))),
+        }
+    }
+
+    pub fn data_info(&mut self, action: i32, colnum: i32, iodesc: &mut CS_IODESC) -> Result<()> {
+        self.conn.diag_clear();
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_data_info(guard.command(self.cmd.id), action, colnum, iodesc);
+        }
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_data_info failed")))
+        }
+    }
+
+    pub fn send_data(&mut self, buffer: &[u8]) -> Result<()> {
+        self.conn.diag_clear();
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_send_data(guard.command(self.cmd.id), buffer.as_ptr() as *mut CS_VOID, buffer.len() as i32);
+        }
+        if ret == CS_SUCCEED {
+            Ok(())
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_send_data failed")))
+        }
+    }
+
     pub fn res_info<T: Default>(&mut self, type_: i32) -> Result<T> {
         self.conn.diag_clear();
 
@@ -165,8 +347,9 @@ impl Command {
         let mut out_len: i32 = Default::default();
         let ret;
         unsafe {
+            let guard = self.conn.lock();
             ret = ct_res_info(
-                self.cmd.handle,
+                guard.command(self.cmd.id),
                 type_,
                 mem::transmute(&mut buf),
                 mem::size_of::<T>() as i32,
@@ -183,13 +366,41 @@ impl Command {
         }
     }
 
+    pub fn compute_info<T: Default>(&mut self, type_: i32, colnum: i32) -> Result<T> {
+        self.conn.diag_clear();
+
+        let mut buf: T = Default::default();
+        let mut out_len: i32 = Default::default();
+        let ret;
+        unsafe {
+            let guard = self.conn.lock();
+            ret = ct_compute_info(
+                guard.command(self.cmd.id),
+                type_,
+                colnum,
+                &mut buf as *mut T as *mut CS_VOID,
+                mem::size_of::<T>() as i32,
+                &mut out_len,
+            );
+        }
+        if ret == CS_SUCCEED {
+            Ok(buf)
+        } else {
+            Err(self
+                .conn
+                .get_error()
+                .unwrap_or_else(|| Error::from_message("ct_compute_info failed")))
+        }
+    }
+
     pub fn describe(&mut self, item: i32) -> Result<CS_DATAFMT> {
         self.conn.diag_clear();
 
         let mut buf: CS_DATAFMT = Default::default();
         let ret;
         unsafe {
-            ret = ct_describe(self.cmd.handle, item, &mut buf);
+            let guard = self.conn.lock();
+            ret = ct_describe(guard.command(self.cmd.id), item, &mut buf);
         }
         if ret == CS_SUCCEED {
             Ok(buf)
@@ -205,7 +416,8 @@ impl Command {
         self.conn.diag_clear();
         let ret;
         unsafe {
-            ret = ct_cancel(ptr::null_mut(), self.cmd.handle, type_);
+            let guard = self.conn.lock();
+            ret = ct_cancel(ptr::null_mut(), guard.command(self.cmd.id), type_);
         }
         if ret == CS_SUCCEED {
             Ok(())
@@ -218,10 +430,14 @@ impl Command {
     }
 }
 
-unsafe impl Send for Command {}
-
+/*
+ * The server connection may be dead by now (e.g. a streamed ResultSet dropped
+ * after its session was killed), so failures are only logged
+ */
 impl Drop for Command {
     fn drop(&mut self) {
-        self.cancel(CS_CANCEL_ALL).expect("cs_cancel failed");
+        if let Err(e) = self.cancel(CS_CANCEL_ALL) {
+            warn!("ct_cancel failed: {}", e);
+        }
     }
 }
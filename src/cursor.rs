@@ -0,0 +1,281 @@
+use crate::command::Command;
+use crate::connection::{Bind, ReadState};
+use crate::result_set::{Column, Rows, SybResult};
+use crate::to_sql::ToSql;
+use crate::{generate_query, parse_query, Connection, Error, ResultSet, Result};
+use freetds_sys::*;
+use log::warn;
+
+/*
+ * A server-side cursor declared with Connection::declare_cursor
+ * Rows are fetched from the server a batch at a time, so large results
+ * can be read without holding them in memory.
+ * Positioned updates and deletes apply to the last row returned by fetch,
+ * the select statement must then be declared "for update".
+ * The cursor is deallocated when dropped.
+ */
+pub struct Cursor {
+    conn: Connection,
+    command: Command,
+    name: String,
+
+    /* rows per fetch */
+    rows: usize,
+    open: bool,
+    deallocated: bool,
+
+    /* columns and binds of the cursor result, until all rows have been fetched */
+    result: Option<(Vec<Column>, Vec<Bind>)>,
+}
+
+impl Cursor {
+    pub(crate) fn declare(conn: &Connection, name: &str, text: &str) -> Result<Self> {
+        let mut cursor = Self {
+            conn: conn.share(),
+            command: Command::new(conn.share()),
+            name: name.to_string(),
+            rows: 1,
+            open: false,
+            deallocated: true,
+            result: None,
+        };
+        cursor.command.cursor(CS_CURSOR_DECLARE, Some(name), Some(text), CS_UNUSED)?;
+        cursor.send()?;
+        cursor.deallocated = false;
+        Ok(cursor)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /*
+     * Open the cursor, each call to fetch then returns up to rows rows
+     * A closed cursor can be opened again, which restarts it from the first row.
+     */
+    pub fn open(&mut self, rows: usize) -> Result<()> {
+        if self.open {
+            return Err(Error::from_message("Cursor is already open"));
+        }
+        if rows == 0 {
+            return Err(Error::from_message("Invalid cursor row count"));
+        }
+
+        self.command.cursor(CS_CURSOR_ROWS, None, None, rows as i32)?;
+        self.command.cursor(CS_CURSOR_OPEN, None, None, CS_UNUSED)?;
+        self.send()?;
+        self.rows = rows;
+        self.open = true;
+        Ok(())
+    }
+
+    /*
+     * Fetch the next batch of rows, returns None once all rows have been read
+     * The ResultSet contains a single row result, read with next and the getters.
+     */
+    pub fn fetch(&mut self) -> Result<Option<ResultSet>> {
+        let (columns, binds) = match self.result.take() {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+
+        let mut rows = Vec::with_capacity(self.rows);
+        let mut finished = false;
+        while rows.len() < self.rows {
+            match Connection::fetch_row(&mut self.command, &columns, &binds)? {
+                Some(row) => rows.push(row),
+                None => {
+                    finished = true;
+                    break;
+                },
+            }
+        }
+
+        let result_columns = columns.clone();
+        if finished {
+            /* Read the end of the cursor result */
+            self.read_results()?;
+        } else {
+            self.result = Some((columns, binds));
+        }
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        let results = vec![SybResult::Rows(Rows::new(result_columns, rows))];
+        Ok(Some(ResultSet::new(self.conn.share(), results, Vec::new(), None)))
+    }
+
+    /*
+     * Update the current row: text is an update statement without a where clause,
+     * such as "update items set price = ?"
+     * Parameters are always interpolated into the text, even with server_side_params:
+     * FreeTDS only accepts ct_param on a cursor command when it is declared or opened.
+     */
+    pub fn update_current(&mut self, table: &str, text: &str, params: &[&dyn ToSql]) -> Result<()> {
+        let parsed_query = parse_query(text);
+        if parsed_query.params.len() != params.len() {
+            return Err(Error::from_message("Invalid parameter count"));
+        }
+        let mut text = String::new();
+        generate_query(&mut text, &parsed_query, params.iter().copied(), self.conn.dialect())
+            .map_err(|e| Error::from_message(e.to_string()))?;
+
+        self.check_open()?;
+        self.command.cursor(CS_CURSOR_UPDATE, Some(table), Some(&text), CS_UNUSED)?;
+        self.send()
+    }
+
+    /*
+     * Delete the current row from table
+     */
+    pub fn delete_current(&mut self, table: &str) -> Result<()> {
+        self.check_open()?;
+        self.command.cursor(CS_CURSOR_DELETE, Some(table), None, CS_UNUSED)?;
+        self.send()
+    }
+
+    /*
+     * Close the cursor, discarding the rows which were not fetched
+     * The cursor remains declared and can be opened again.
+     */
+    pub fn close(&mut self) -> Result<()> {
+        self.check_open()?;
+        self.open = false;
+        self.result = None;
+        self.command.cursor(CS_CURSOR_CLOSE, None, None, CS_UNUSED)?;
+        self.send()
+    }
+
+    /*
+     * Close the cursor if it is open and release it on the server
+     */
+    pub fn deallocate(mut self) -> Result<()> {
+        self.dealloc()
+    }
+
+    fn dealloc(&mut self) -> Result<()> {
+        if self.deallocated {
+            return Ok(());
+        }
+        self.deallocated = true;
+        self.result = None;
+        if self.open {
+            self.open = false;
+            self.command.cursor(CS_CURSOR_CLOSE, None, None, CS_DEALLOC)?;
+        } else {
+            self.command.cursor(CS_CURSOR_DEALLOC, None, None, CS_UNUSED)?;
+        }
+        self.send()
+    }
+
+    fn check_open(&self) -> Result<()> {
+        if self.open {
+            Ok(())
+        } else {
+            Err(Error::from_message("Cursor is not open"))
+        }
+    }
+
+    fn send(&mut self) -> Result<()> {
+        self.command.send()?;
+        self.read_results()
+    }
+
+    /*
+     * Read the results of the last cursor command, up to the cursor result if there is one
+     */
+    fn read_results(&mut self) -> Result<()> {
+        let mut state = ReadState {
+            streaming: false,
+            unbound: false,
+            fail_on_status: false,
+            failed: false,
+            errors: Vec::new(),
+        };
+        while let Some((result, binds)) = self.conn.read_result(&mut self.command, &mut state)? {
+            if let (SybResult::Rows(rows), Some(binds)) = (result, binds) {
+                self.result = Some((rows.columns, binds));
+                break;
+            }
+        }
+
+        if state.failed {
+            return Err(Connection::get_single_error(&state.errors)
+                .unwrap_or_else(|| Error::from_message("Cursor command failed")));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        if let Err(e) = self.dealloc() {
+            warn!("Cannot deallocate cursor {}: {}", self.name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{connect, scratch_table};
+
+    #[test]
+    fn test_cursor() {
+        let mut conn = connect();
+        scratch_table(&mut conn, "freetds_cursor", "id int primary key, name varchar(32)");
+        for i in 0..5 {
+            conn.execute("insert into freetds_cursor values(?, ?)", &[&i, &format!("row {}", i)]).unwrap();
+        }
+
+        let mut cursor = conn
+            .declare_cursor("freetds_c1", "select id, name from freetds_cursor order by id for update of name")
+            .unwrap();
+        assert!(cursor.fetch().unwrap().is_none());
+        cursor.open(2).unwrap();
+        assert!(cursor.is_open());
+
+        let mut rs = cursor.fetch().unwrap().unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(0), rs.get_i32(0).unwrap());
+        assert!(rs.next());
+        assert_eq!(Some(1), rs.get_i32("id").unwrap());
+        assert!(!rs.next());
+        cursor.update_current("freetds_cursor", "update freetds_cursor set name = ?", &[&"updated"]).unwrap();
+
+        let mut rs = cursor.fetch().unwrap().unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(2), rs.get_i32(0).unwrap());
+
+        /* The current row is the last row of the batch */
+        cursor.delete_current("freetds_cursor").unwrap();
+
+        let mut count = 0;
+        while let Some(mut rs) = cursor.fetch().unwrap() {
+            while rs.next() {
+                count += 1;
+            }
+        }
+        assert_eq!(1, count);
+        cursor.close().unwrap();
+        assert!(cursor.close().is_err());
+
+        /* Reopened from the first row */
+        cursor.open(10).unwrap();
+        let mut rs = cursor.fetch().unwrap().unwrap();
+        let mut ids = Vec::new();
+        while rs.next() {
+            ids.push(rs.get_i32(0).unwrap().unwrap());
+        }
+        assert_eq!(vec![0, 1, 2, 4], ids);
+        cursor.deallocate().unwrap();
+
+        let mut rs = conn.execute("select name from freetds_cursor where id = 1", &[]).unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(String::from("updated")), rs.get_string(0).unwrap());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{Error, ResultSet, Result, Value};
+use rust_decimal::prelude::ToPrimitive;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::fmt::Display;
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::from_message(msg.to_string())
+    }
+}
+
+/*
+ * Deserializer over the current row of a ResultSet, see ResultSet::deserialize
+ * Structs and maps are filled by column name, sequences and tuples by column order.
+ */
+pub struct RowDeserializer {
+    columns: Vec<(String, Value)>,
+}
+
+impl RowDeserializer {
+    pub(crate) fn new(rs: &mut ResultSet) -> Result<Self> {
+        let count = rs.column_count()?;
+        let mut columns = Vec::with_capacity(count);
+        for i in 0..count {
+            columns.push((rs.column_name(i)?, rs.get_value(i)?));
+        }
+        Ok(Self { columns })
+    }
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut map = MapDeserializer::new(self.columns.into_iter());
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut seq = SeqDeserializer::new(self.columns.into_iter().map(|(_, value)| value));
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = ValueDeserializer;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        ValueDeserializer { value: self }
+    }
+}
+
+/*
+ * Deserializer over a single column value
+ * Decimals, dates and times are passed as strings, which is what their
+ * own Deserialize implementations expect.
+ */
+pub struct ValueDeserializer {
+    value: Value,
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::String(s) => visitor.visit_string(s),
+            Value::I32(i) => visitor.visit_i32(i),
+            Value::I64(i) => visitor.visit_i64(i),
+            Value::F64(f) => visitor.visit_f64(f),
+            Value::Decimal(d) => visitor.visit_string(d.to_string()),
+            Value::Date(d) => visitor.visit_string(d.format("%Y-%m-%d").to_string()),
+            Value::Time(t) => visitor.visit_string(t.format("%H:%M:%S%.f").to_string()),
+            Value::DateTime(dt) => visitor.visit_string(dt.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+            Value::Blob(b) => visitor.visit_byte_buf(b),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::I32(i) => visitor.visit_bool(i != 0),
+            Value::I64(i) => visitor.visit_bool(i != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Decimal(d) => match d.to_f64() {
+                Some(f) => visitor.visit_f64(f),
+                None => Err(de::Error::custom(format!("Cannot convert {} to f64", d))),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /*
+     * Unit variants, stored as their name
+     */
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.value {
+            Value::String(s) => visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(s)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::connect;
+    use crate::{Decimal, NaiveDate, Value};
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+    use std::str::FromStr;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Small,
+        Large,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: i64,
+        name: String,
+        price: Decimal,
+        ratio: f64,
+        created: NaiveDate,
+        comment: Option<String>,
+        active: bool,
+        kind: Kind,
+    }
+
+    #[test]
+    fn test_value_deserializer() {
+        let d = Decimal::from_str("12.50").unwrap();
+        assert_eq!(d, <Decimal as Deserialize>::deserialize(Value::from(d).into_deserializer()).unwrap());
+        assert_eq!(12.5, f64::deserialize(Value::from(d).into_deserializer()).unwrap());
+        assert_eq!(None, Option::<i32>::deserialize(Value::Null.into_deserializer()).unwrap());
+        assert_eq!(Some(7u8), Option::<u8>::deserialize(Value::from(7).into_deserializer()).unwrap());
+        assert!(u8::deserialize(Value::from(300).into_deserializer()).is_err());
+        assert!(bool::deserialize(Value::from(1).into_deserializer()).unwrap());
+        assert_eq!(Kind::Large, Kind::deserialize(Value::from("Large").into_deserializer()).unwrap());
+        let date = NaiveDate::from_ymd_opt(2023, 4, 5).unwrap();
+        assert_eq!(date, NaiveDate::deserialize(Value::from(date).into_deserializer()).unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_row() {
+        let mut conn = connect();
+        let mut rs = conn
+            .execute(
+                "select 1 as id, 'abc' as name, cast(12.50 as numeric(10,2)) as price, cast(12.50 as numeric(10,2)) as ratio, \
+                cast('2023-04-05' as date) as created, cast(null as varchar(10)) as comment, 1 as active, 'Small' as kind, \
+                'ignored' as extra",
+                &[],
+            )
+            .unwrap();
+        assert!(rs.next());
+        let item: Item = rs.deserialize().unwrap();
+        assert_eq!(
+            Item {
+                id: 1,
+                name: String::from("abc"),
+                price: Decimal::from_str("12.50").unwrap(),
+                ratio: 12.5,
+                created: NaiveDate::from_ymd_opt(2023, 4, 5).unwrap(),
+                comment: None,
+                active: true,
+                kind: Kind::Small,
+            },
+            item
+        );
+
+        /* Tuples must have one element per column */
+        assert!(rs.deserialize::<(i32, String)>().is_err());
+        let (id, name, ..): (i32, String, Decimal, f64, String, Option<String>, i32, String, String) = rs.deserialize().unwrap();
+        assert_eq!((1, String::from("abc")), (id, name));
+    }
+}
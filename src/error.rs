@@ -20,12 +20,53 @@ impl Default for Type {
     }
 }
 
+/*
+ * Classification of the common server and client errors, see Error::kind
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    DeadlockVictim,
+    DuplicateKey,
+    Timeout,
+    LoginFailed,
+    ConnectionDead,
+    PermissionDenied,
+    ObjectNotFound,
+    ConstraintViolation,
+    Conversion,
+    Cancelled,
+    Other,
+}
+
 #[derive(Debug, Clone)]
 pub struct Error {
     pub(crate) type_: Type,
     pub(crate) code: Option<i32>,
     pub(crate) desc: String,
     pub(crate) severity: Option<i32>,
+    pub(crate) cancelled: bool,
+
+    /* Boxed so that Results stay small */
+    pub(crate) details: Option<Box<Details>>,
+}
+
+/*
+ * Additional fields of server and client messages
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Details {
+    /* Server messages */
+    pub state: Option<i32>,
+    pub line: Option<i32>,
+    pub proc_name: Option<String>,
+    pub server: Option<String>,
+
+    /* Server and client messages */
+    pub sqlstate: Option<String>,
+
+    /* Client messages caused by an operating system error */
+    pub os_code: Option<i32>,
+    pub os_desc: Option<String>,
 }
 
 impl Error {
@@ -35,15 +76,22 @@ impl Error {
             code: None,
             desc: desc.as_ref().to_string(),
             severity: None,
+            cancelled: false,
+            details: None,
         }
     }
 
     pub fn from_failure(fn_name: impl AsRef<str>) -> Self {
+        Self::from_message(format!("{} failed", fn_name.as_ref()))
+    }
+
+    /*
+     * Returned by the call which was interrupted through a CancelHandle
+     */
+    pub fn cancelled() -> Self {
         Self {
-            type_: Default::default(),
-            code: None,
-            desc: format!("{} failed", fn_name.as_ref()),
-            severity: None,
+            cancelled: true,
+            ..Self::from_message("Query was cancelled")
         }
     }
 
@@ -62,6 +110,110 @@ impl Error {
     pub fn severity(&self) -> Option<i32> {
         self.severity
     }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        if self.cancelled {
+            return ErrorKind::Cancelled;
+        }
+        let code = match self.code {
+            Some(code) => code,
+            None => return ErrorKind::Other,
+        };
+
+        match self.type_ {
+            Type::Server => match code {
+                1205 => ErrorKind::DeadlockVictim,
+                /* lock request timeout: 1222 on MSSQL, 12205 on ASE */
+                1222 | 12205 => ErrorKind::Timeout,
+                2601 | 2615 | 2627 => ErrorKind::DuplicateKey,
+                4002 | 18456 => ErrorKind::LoginFailed,
+                229 | 230 | 262 | 10330 | 10331 => ErrorKind::PermissionDenied,
+                207 | 208 | 2812 | 3701 => ErrorKind::ObjectNotFound,
+                233 | 515 | 546 | 547 | 548 | 552 => ErrorKind::ConstraintViolation,
+                245 | 247 | 249 | 257 | 3606 | 8114 | 8115 => ErrorKind::Conversion,
+                _ => ErrorKind::Other,
+            },
+
+            /* FreeTDS passes the TDS layer error numbers (TDSE*) through */
+            Type::Client => match code {
+                /* TDSETIME */
+                20003 => ErrorKind::Timeout,
+                20014 => ErrorKind::LoginFailed,
+                20002 | 20004 | 20006 | 20009 | 20017 => ErrorKind::ConnectionDead,
+                _ => ErrorKind::Other,
+            },
+
+            /*
+             * CS-Library message numbers hold the layer in the high byte and the number in the low byte.
+             * User API layer (2): unsupported conversion (16), overflow (20), syntax error (24)
+             */
+            Type::Cs => match ((code >> 24) & 0xff, code & 0xff) {
+                (2, 16 | 20 | 24) => ErrorKind::Conversion,
+                _ => ErrorKind::Other,
+            },
+            Type::Library => ErrorKind::Other,
+        }
+    }
+
+    /*
+     * Whether running the same statement again may succeed:
+     * deadlocks and timeouts, and dead connections once reconnected
+     */
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::DeadlockVictim | ErrorKind::Timeout | ErrorKind::ConnectionDead
+        )
+    }
+
+    pub fn is_deadlock(&self) -> bool {
+        self.kind() == ErrorKind::DeadlockVictim
+    }
+
+    pub fn is_duplicate_key(&self) -> bool {
+        self.kind() == ErrorKind::DuplicateKey
+    }
+
+    pub fn state(&self) -> Option<i32> {
+        self.details.as_ref().and_then(|details| details.state)
+    }
+
+    /*
+     * Line of the batch or stored procedure which raised the message
+     */
+    pub fn line(&self) -> Option<i32> {
+        self.details.as_ref().and_then(|details| details.line)
+    }
+
+    /*
+     * Stored procedure which raised the message
+     */
+    pub fn proc_name(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|details| details.proc_name.as_deref())
+    }
+
+    pub fn server(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|details| details.server.as_deref())
+    }
+
+    pub fn sqlstate(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|details| details.sqlstate.as_deref())
+    }
+
+    /*
+     * Operating system error number, for client messages caused by a network failure
+     */
+    pub fn os_code(&self) -> Option<i32> {
+        self.details.as_ref().and_then(|details| details.os_code)
+    }
+
+    pub fn os_desc(&self) -> Option<&str> {
+        self.details.as_ref().and_then(|details| details.os_desc.as_deref())
+    }
 }
 
 impl Display for Error {
@@ -89,7 +241,34 @@ impl Display for Error {
             write!(f, " severity {}", severity)?;
         }
 
-        write!(f, ": {}", self.desc)
+        write!(f, ": {}", self.desc)?;
+
+        let mut details = Vec::new();
+        if let Some(server) = self.server() {
+            details.push(format!("server {}", server));
+        }
+        if let Some(proc_name) = self.proc_name() {
+            details.push(format!("procedure {}", proc_name));
+        }
+        if let Some(line) = self.line() {
+            details.push(format!("line {}", line));
+        }
+        if let Some(state) = self.state() {
+            details.push(format!("state {}", state));
+        }
+        if let Some(sqlstate) = self.sqlstate() {
+            details.push(format!("sqlstate {}", sqlstate));
+        }
+        if let Some(os_code) = self.os_code() {
+            match self.os_desc() {
+                Some(os_desc) => details.push(format!("os error {}: {}", os_code, os_desc)),
+                None => details.push(format!("os error {}", os_code)),
+            }
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -106,3 +285,89 @@ impl From<std::ffi::NulError> for Error {
         Self::from_message(e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Details, Error, ErrorKind, Type};
+    use crate::tests::connect;
+
+    #[test]
+    fn test_display() {
+        let mut error = Error::from_message("Something failed");
+        assert_eq!("FreeTDS error: Something failed", error.to_string());
+
+        error.type_ = Type::Server;
+        error.code = Some(2601);
+        error.severity = Some(14);
+        error.details = Some(Box::new(Details {
+            server: Some(String::from("SYBASE")),
+            proc_name: Some(String::from("sp_insert_item")),
+            line: Some(42),
+            state: Some(1),
+            sqlstate: Some(String::from("23000")),
+            ..Default::default()
+        }));
+        assert_eq!(
+            "Server error #2601 severity 14: Something failed \
+            (server SYBASE, procedure sp_insert_item, line 42, state 1, sqlstate 23000)",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_kind() {
+        fn error(type_: Type, code: i32) -> Error {
+            Error {
+                type_,
+                code: Some(code),
+                ..Error::from_message("")
+            }
+        }
+
+        assert_eq!(ErrorKind::DeadlockVictim, error(Type::Server, 1205).kind());
+        assert!(error(Type::Server, 1205).is_retryable());
+        assert!(error(Type::Server, 1205).is_deadlock());
+        assert!(error(Type::Server, 2627).is_duplicate_key());
+        assert!(!error(Type::Server, 2601).is_retryable());
+        assert_eq!(ErrorKind::ObjectNotFound, error(Type::Server, 208).kind());
+        assert_eq!(ErrorKind::Timeout, error(Type::Client, 20003).kind());
+        assert_eq!(ErrorKind::ConnectionDead, error(Type::Client, 20006).kind());
+        assert!(error(Type::Client, 20006).is_retryable());
+        assert_eq!(ErrorKind::Timeout, error(Type::Server, 1222).kind());
+        assert_eq!(ErrorKind::Timeout, error(Type::Server, 12205).kind());
+        assert!(error(Type::Server, 12205).is_retryable());
+
+        /* Only the client message number is used, not the text */
+        let timed_out = Error {
+            type_: Type::Client,
+            code: Some(20001),
+            ..Error::from_message("Operation timed out")
+        };
+        assert_eq!(ErrorKind::Other, timed_out.kind());
+
+        /* CS-Library layer 2, origin 1, severity 1 */
+        assert_eq!(ErrorKind::Conversion, error(Type::Cs, 0x0201_0118).kind());
+        assert_eq!(ErrorKind::Conversion, error(Type::Cs, 0x0201_0110).kind());
+        assert_eq!(ErrorKind::Other, error(Type::Cs, 0x0201_0103).kind());
+
+        /* Server and client numbers overlap, user messages start at 20000 */
+        assert_eq!(ErrorKind::Other, error(Type::Server, 20003).kind());
+        assert_eq!(ErrorKind::Cancelled, Error::cancelled().kind());
+        assert_eq!(ErrorKind::Other, Error::from_message("Invalid column index").kind());
+    }
+
+    #[test]
+    fn test_server_message_details() {
+        let mut conn = connect();
+        conn.execute("use tempdb", &[]).unwrap();
+        conn.execute("if exists(select * from tempdb..sysobjects where name='freetds_fail') drop procedure freetds_fail", &[]).unwrap();
+        conn.execute("create procedure freetds_fail as\nbegin\n    select 1\n    raiserror 20001 'Procedure failed'\nend", &[]).unwrap();
+
+        let error = conn.execute("exec freetds_fail", &[]).err().unwrap();
+        assert_eq!(Some(20001), error.code());
+        assert_eq!(Some("freetds_fail"), error.proc_name());
+        assert_eq!(Some(4), error.line());
+        assert!(error.server().is_some());
+        assert!(error.to_string().contains("procedure freetds_fail, line 4"));
+    }
+}
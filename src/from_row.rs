@@ -0,0 +1,50 @@
+use crate::{ResultSet, Result};
+
+/*
+ * Types built from the current row of a ResultSet, see ResultSet::collect_as
+ * With the derive feature, #[derive(FromRow)] maps the columns to struct fields by name.
+ */
+pub trait FromRow: Sized {
+    fn from_row(rs: &mut ResultSet) -> Result<Self>;
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::tests::connect;
+    use crate::FromRow;
+
+    #[derive(FromRow, Debug, PartialEq)]
+    struct Item {
+        id: i32,
+        #[freetds(rename = "item_name")]
+        name: String,
+        comment: Option<String>,
+        #[freetds(default)]
+        quantity: i32,
+        #[freetds(default)]
+        missing: String,
+    }
+
+    #[test]
+    fn test_derive_from_row() {
+        let mut conn = connect();
+        let mut rs = conn
+            .execute(
+                "select 1 as id, 'first' as item_name, cast(null as varchar(10)) as comment, cast(null as int) as quantity \
+                union all select 2, 'second', 'abc', 5",
+                &[],
+            )
+            .unwrap();
+        let items: Vec<Item> = rs.collect_as().unwrap();
+        assert_eq!(
+            vec![
+                Item { id: 1, name: String::from("first"), comment: None, quantity: 0, missing: String::new() },
+                Item { id: 2, name: String::from("second"), comment: Some(String::from("abc")), quantity: 5, missing: String::new() },
+            ],
+            items
+        );
+
+        let mut rs = conn.execute("select 1 as id", &[]).unwrap();
+        assert!(rs.collect_as::<Item>().is_err());
+    }
+}
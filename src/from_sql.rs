@@ -0,0 +1,135 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use crate::{ColumnId, ResultSet, Result, Value};
+
+/*
+ * Types which can be read from a column of the current row with ResultSet::get
+ * Implementations typically start from one of the existing types, for example
+ * String::from_sql for an enum stored as a character code.
+ */
+pub trait FromSql: Sized {
+    /*
+     * Returns None when the column is null
+     */
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>>;
+}
+
+impl FromSql for i32 {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_i32(col)
+    }
+}
+
+impl FromSql for i64 {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_i64(col)
+    }
+}
+
+impl FromSql for f64 {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_f64(col)
+    }
+}
+
+impl FromSql for bool {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_bool(col)
+    }
+}
+
+impl FromSql for String {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_string(col)
+    }
+}
+
+impl FromSql for NaiveDate {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_date(col)
+    }
+}
+
+impl FromSql for NaiveTime {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_time(col)
+    }
+}
+
+impl FromSql for NaiveDateTime {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_datetime(col)
+    }
+}
+
+impl FromSql for Decimal {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_decimal(col)
+    }
+}
+
+impl FromSql for Vec<u8> {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        rs.get_blob(col)
+    }
+}
+
+impl FromSql for Value {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        match rs.get_value(col)? {
+            Value::Null => Ok(None),
+            value => Ok(Some(value)),
+        }
+    }
+}
+
+/*
+ * Nullable columns: get::<Option<T>> returns Ok(None) instead of an error for null values
+ */
+impl<T: FromSql> FromSql for Option<T> {
+    fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+        Ok(Some(T::from_sql(rs, col)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FromSql;
+    use crate::tests::connect;
+    use crate::{ColumnId, Error, ResultSet, Result, Value};
+
+    #[derive(Debug, PartialEq)]
+    enum Status {
+        Active,
+        Closed,
+    }
+
+    impl FromSql for Status {
+        fn from_sql(rs: &mut ResultSet, col: ColumnId) -> Result<Option<Self>> {
+            match String::from_sql(rs, col)?.as_deref() {
+                None => Ok(None),
+                Some("A") => Ok(Some(Status::Active)),
+                Some("C") => Ok(Some(Status::Closed)),
+                Some(code) => Err(Error::from_message(format!("Invalid status: {}", code))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let mut conn = connect();
+        let mut rs = conn
+            .execute("select 42 as id, 'abc' as name, cast(null as int) as missing, 'C' as status, 'X'", &[])
+            .unwrap();
+        assert!(rs.next());
+        assert_eq!(42, rs.get::<i32>(0).unwrap());
+        assert_eq!(42i64, rs.get("id").unwrap());
+        assert_eq!("abc", rs.get::<String>("name").unwrap());
+        assert_eq!(Value::from("abc"), rs.get::<Value>(1).unwrap());
+        assert_eq!(None, rs.get::<Option<i32>>("missing").unwrap());
+        assert_eq!(Some(42), rs.get::<Option<i32>>("id").unwrap());
+        assert!(rs.get::<i32>("missing").is_err());
+        assert_eq!(Status::Closed, rs.get::<Status>("status").unwrap());
+        assert!(rs.get::<Status>(4).is_err());
+    }
+}
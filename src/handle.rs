@@ -0,0 +1,84 @@
+/*
+ * Identifies a command or bulk copy handle owned by a CSConnection
+ * The handle itself stays in the CSConnection, so it can only be obtained
+ * through the Connection lock.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HandleId(usize);
+
+/*
+ * CT-Library handles of one kind, indexed by HandleId
+ * Freed slots are null and reused.
+ */
+pub(crate) struct Handles<T> {
+    slots: Vec<*mut T>,
+}
+
+impl<T> Default for Handles<T> {
+    fn default() -> Self {
+        Self { slots: Vec::new() }
+    }
+}
+
+impl<T> Handles<T> {
+    pub fn insert(&mut self, handle: *mut T) -> HandleId {
+        match self.slots.iter().position(|slot| slot.is_null()) {
+            Some(index) => {
+                self.slots[index] = handle;
+                HandleId(index)
+            },
+            None => {
+                self.slots.push(handle);
+                HandleId(self.slots.len() - 1)
+            },
+        }
+    }
+
+    pub fn get(&self, id: HandleId) -> *mut T {
+        let handle = self.slots[id.0];
+        assert!(!handle.is_null(), "Handle used after being removed");
+        handle
+    }
+
+    pub fn remove(&mut self, id: HandleId) -> *mut T {
+        let handle = self.get(id);
+        self.slots[id.0] = std::ptr::null_mut();
+        handle
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| !slot.is_null()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Handles;
+
+    #[test]
+    fn test_handles() {
+        let mut values = [1, 2, 3];
+        let mut handles: Handles<i32> = Default::default();
+        let a = handles.insert(&mut values[0]);
+        let b = handles.insert(&mut values[1]);
+        assert_eq!(2, handles.len());
+        assert_eq!(&mut values[1] as *mut i32, handles.get(b));
+
+        /* Freed slots are reused */
+        handles.remove(a);
+        assert_eq!(1, handles.len());
+        let c = handles.insert(&mut values[2]);
+        assert_eq!(a, c);
+        assert_eq!(&mut values[2] as *mut i32, handles.get(c));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_removed_handle() {
+        let mut value = 1;
+        let mut handles: Handles<i32> = Default::default();
+        let id = handles.insert(&mut value);
+        handles.remove(id);
+        handles.get(id);
+    }
+}
@@ -1,21 +1,59 @@
+/* Lets the code generated by freetds-derive refer to ::freetds from within this crate */
+extern crate self as freetds;
+
+#[cfg(feature = "async")]
+pub mod async_connection;
+pub mod blob;
+pub mod bulk;
+pub mod cancel;
 pub mod column_id;
+pub mod column_info;
 pub(crate) mod command;
 pub mod connection;
+pub mod cursor;
+pub mod de;
 pub mod error;
+pub mod from_row;
+pub mod from_sql;
+pub(crate) mod handle;
+pub(crate) mod param;
+pub mod pool;
+pub mod prepared_statement;
+pub mod procedure;
 pub mod result_set;
+pub mod retry;
+pub mod ser;
 pub mod statement;
 pub mod to_sql;
+pub mod transaction;
 pub mod util;
 pub mod value;
 
+#[cfg(feature = "async")]
+pub use async_connection::AsyncConnection;
+pub use blob::{BlobReader, BlobRows, BlobWriter, TextPointer};
+pub use bulk::BulkInsert;
+pub use cancel::CancelHandle;
 pub use column_id::ColumnId;
-pub use connection::{Connection,TdsVersion};
-pub use error::Error;
-pub use result_set::{ResultSet, ResultType};
+pub use column_info::{ColumnInfo, DataType};
+pub use connection::{Connection, Encryption, ServerType, TdsVersion};
+pub use cursor::Cursor;
+pub use error::{Error, ErrorKind};
+pub use from_row::FromRow;
+#[cfg(feature = "derive")]
+pub use freetds_derive::FromRow;
+pub use from_sql::FromSql;
+pub use pool::Pool;
+pub use prepared_statement::PreparedStatement;
+pub use procedure::ProcParam;
+pub use result_set::{ComputeOp, ResultSet, ResultType};
+pub use retry::RetryPolicy;
+pub use to_sql::Dialect;
 use to_sql::ToSql;
 pub type Result<T, E = error::Error> = core::result::Result<T, E>;
 pub use rust_decimal::Decimal;
 pub use statement::Statement;
+pub use transaction::Transaction;
 pub use value::Value;
 pub use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
 
@@ -142,7 +180,7 @@ pub(crate) fn parse_query(text: impl AsRef<str>) -> ParsedQuery {
     ParsedQuery { pieces, params }
 }
 
-pub(crate) fn generate_query<'a, I>(f: &mut dyn std::fmt::Write, query: &ParsedQuery, mut params: I) -> std::fmt::Result
+pub(crate) fn generate_query<'a, I>(f: &mut dyn std::fmt::Write, query: &ParsedQuery, mut params: I, dialect: Dialect) -> std::fmt::Result
 where
     I: Iterator<Item = &'a dyn ToSql>,
 {
@@ -151,7 +189,7 @@ where
             TextPiece::Literal(s) => f.write_str(s)?,
             TextPiece::Placeholder => {
                 match params.next() {
-                    Some(value) => value.to_sql(f)?,
+                    Some(value) => value.to_sql_dialect(f, dialect)?,
                     None => f.write_str("null")?,
                 }
             },
@@ -160,9 +198,47 @@ where
     Ok(())
 }
 
+/*
+ * Generate query text where placeholders are replaced with @-prefixed parameter names,
+ * so that values can be sent with ct_param instead of being interpolated.
+ * Returns the distinct parameter names, each with the index of the value to bind to it.
+ * Named placeholders which appear more than once share the same parameter.
+ */
+pub(crate) fn generate_bound_query(f: &mut dyn std::fmt::Write, query: &ParsedQuery) -> Result<Vec<(String, usize)>, std::fmt::Error> {
+    let mut bindings: Vec<(String, usize)> = Vec::new();
+    let mut next_positional = 1;
+    let mut params = query.params.iter().enumerate();
+    for piece in &query.pieces {
+        match piece {
+            TextPiece::Literal(s) => f.write_str(s)?,
+            TextPiece::Placeholder => {
+                let (index, param) = params.next().ok_or(std::fmt::Error)?;
+                let name = match param {
+                    Some(name) => format!("@{}", name),
+                    None => {
+                        /* Skip names already taken by named placeholders */
+                        let mut name = format!("@p{}", next_positional);
+                        while query.params.iter().flatten().any(|p| *p == name[1..]) {
+                            next_positional += 1;
+                            name = format!("@p{}", next_positional);
+                        }
+                        next_positional += 1;
+                        name
+                    }
+                };
+                f.write_str(&name)?;
+                if !bindings.iter().any(|(n, _)| *n == name) {
+                    bindings.push((name, index));
+                }
+            }
+        }
+    }
+    Ok(bindings)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{parse_query, Connection, TextPiece, connection::TdsVersion};
+    use crate::{generate_bound_query, parse_query, Connection, TextPiece, connection::{ConnectionBuilder, TdsVersion}};
 
     pub fn get_test_server() -> (String,u16) {
         let server = std::fs::read_to_string("test-server.txt").unwrap().trim().to_string();
@@ -173,7 +249,7 @@ mod tests {
         )
     }
 
-    pub fn connect() -> Connection {
+    pub fn builder() -> ConnectionBuilder {
         let (server, port) = get_test_server();
         Connection::builder()
             .host(&server)
@@ -184,10 +260,23 @@ mod tests {
             .tds_version(TdsVersion::Tds50)
             .login_timeout(5)
             .timeout(5)
+    }
+
+    pub fn connect() -> Connection {
+        builder()
             .connect()
             .unwrap()
     }
 
+    /*
+     * Switch to tempdb and create an empty table there, dropping any previous one
+     */
+    pub fn scratch_table(conn: &mut Connection, name: &str, columns: &str) {
+        conn.execute("use tempdb", &[]).unwrap();
+        conn.execute(format!("if exists(select * from tempdb..sysobjects where name='{}') drop table {}", name, name), &[]).unwrap();
+        conn.execute(format!("create table {}({})", name, columns), &[]).unwrap();
+    }
+
     #[test]
     fn test_named_param() {
         let s = ":param";
@@ -269,6 +358,23 @@ mod tests {
         assert_eq!(s, concated);
     }
 
+    #[test]
+    fn test_bound_query() {
+        let query = parse_query("select ?, :p1, ?, '?', :name, :name");
+        let mut text = String::new();
+        let bindings = generate_bound_query(&mut text, &query).unwrap();
+        assert_eq!("select @p2, @p1, @p3, '?', @name, @name", text);
+        assert_eq!(
+            vec![
+                (String::from("@p2"), 0),
+                (String::from("@p1"), 1),
+                (String::from("@p3"), 2),
+                (String::from("@name"), 3),
+            ],
+            bindings
+        );
+    }
+
     #[test]
     fn test_quotes() {
         let mut conn = connect();
@@ -0,0 +1,241 @@
+use crate::{Connection, Error, Result, Value};
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use freetds_sys::*;
+use std::mem;
+
+/*
+ * Largest value which fits in a CS_CHAR/CS_BINARY parameter
+ * Longer values are sent as CS_LONGCHAR/CS_LONGBINARY
+ */
+const SHORT_DATA_LEN: usize = 255;
+
+/*
+ * Room left for the value returned in a CS_LONGCHAR/CS_LONGBINARY output parameter
+ */
+const LONG_DATA_LEN: usize = 32768;
+
+/*
+ * A value converted to its CT-Library representation, ready to be passed to ct_param
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct Param {
+    pub fmt: CS_DATAFMT,
+    pub buffer: Vec<u8>,
+    pub indicator: i16,
+}
+
+impl Param {
+    pub fn new(conn: &mut Connection, value: &Value) -> Result<Self> {
+        let mut fmt = CS_DATAFMT {
+            status: CS_INPUTVALUE,
+            count: 1,
+            ..Default::default()
+        };
+        let mut indicator = 0;
+
+        let buffer: Vec<u8> = match value {
+            Value::Null => {
+                fmt.datatype = CS_CHAR_TYPE;
+                indicator = -1;
+                Vec::new()
+            }
+            Value::String(s) => {
+                fmt.datatype = if s.len() > SHORT_DATA_LEN {
+                    CS_LONGCHAR_TYPE
+                } else {
+                    CS_CHAR_TYPE
+                };
+                Vec::from(s.as_bytes())
+            }
+            Value::I32(i) => {
+                fmt.datatype = CS_INT_TYPE;
+                i.to_ne_bytes().to_vec()
+            }
+            Value::I64(i) => {
+                fmt.datatype = CS_BIGINT_TYPE;
+                i.to_ne_bytes().to_vec()
+            }
+            Value::F64(f) => {
+                fmt.datatype = CS_FLOAT_TYPE;
+                f.to_ne_bytes().to_vec()
+            }
+            Value::Decimal(d) => {
+                /*
+                 * Let cs_convert build the CS_NUMERIC from the decimal's textual representation
+                 */
+                let text = d.to_string();
+                let digits = d.mantissa().unsigned_abs().to_string().len() as i32;
+                let scale = d.scale() as i32;
+                fmt.datatype = CS_NUMERIC_TYPE;
+                fmt.precision = digits.max(scale).max(1);
+                fmt.scale = scale;
+                fmt.maxlength = mem::size_of::<CS_NUMERIC>() as i32;
+
+                let srcfmt = CS_DATAFMT {
+                    datatype: CS_CHAR_TYPE,
+                    maxlength: text.len() as i32,
+                    format: CS_FMT_UNUSED as i32,
+                    count: 1,
+                    ..Default::default()
+                };
+                let mut buffer = vec![0u8; fmt.maxlength as usize];
+                let len = conn.convert(&srcfmt, text.as_bytes(), &fmt, &mut buffer)?;
+                buffer.truncate(len);
+                buffer
+            }
+            Value::Date(d) => {
+                fmt.datatype = CS_DATE_TYPE;
+                days_since_epoch(d)?.to_ne_bytes().to_vec()
+            }
+            Value::Time(t) => {
+                fmt.datatype = CS_TIME_TYPE;
+                time_ticks(t).to_ne_bytes().to_vec()
+            }
+            Value::DateTime(dt) => {
+                fmt.datatype = CS_DATETIME_TYPE;
+                let mut buffer = days_since_epoch(&dt.date())?.to_ne_bytes().to_vec();
+                buffer.extend_from_slice(&time_ticks(&dt.time()).to_ne_bytes());
+                debug_assert_eq!(mem::size_of::<CS_DATETIME>(), buffer.len());
+                buffer
+            }
+            Value::Blob(b) => {
+                fmt.datatype = if b.len() > SHORT_DATA_LEN {
+                    CS_LONGBINARY_TYPE
+                } else {
+                    CS_BINARY_TYPE
+                };
+                b.clone()
+            }
+        };
+
+        if fmt.maxlength == 0 {
+            fmt.maxlength = buffer.len() as i32;
+        }
+
+        Ok(Self {
+            fmt,
+            buffer,
+            indicator,
+        })
+    }
+
+    /*
+     * Send a NULL value as the given CS_*_TYPE instead of CS_CHAR_TYPE
+     */
+    pub fn null_type(mut self, datatype: i32) -> Self {
+        if self.indicator == -1 {
+            self.fmt.datatype = datatype;
+            if datatype == CS_NUMERIC_TYPE || datatype == CS_DECIMAL_TYPE {
+                /* Largest precision supported by both ASE and SQL Server */
+                self.fmt.precision = 38;
+                self.fmt.scale = 0;
+                self.fmt.maxlength = mem::size_of::<CS_NUMERIC>() as i32;
+            }
+        }
+        self
+    }
+
+    /*
+     * Mark the parameter as a stored procedure output parameter
+     */
+    pub fn output(mut self) -> Self {
+        self.fmt.status = CS_RETURN;
+
+        /* Leave room for the returned value */
+        match self.fmt.datatype {
+            CS_CHAR_TYPE | CS_VARCHAR_TYPE | CS_UNICHAR_TYPE | CS_BINARY_TYPE | CS_VARBINARY_TYPE => {
+                self.fmt.maxlength = SHORT_DATA_LEN as i32;
+            }
+            CS_LONGCHAR_TYPE | CS_LONGBINARY_TYPE => {
+                self.fmt.maxlength = self.fmt.maxlength.max(LONG_DATA_LEN as i32);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /*
+     * Parameter name, including the leading '@'
+     */
+    pub fn name(mut self, name: &str) -> Result<Self> {
+        if name.len() > self.fmt.name.len() {
+            return Err(Error::from_message(format!("Parameter name too long: {}", name)));
+        }
+        for (dst, src) in self.fmt.name.iter_mut().zip(name.bytes()) {
+            *dst = src as _;
+        }
+        self.fmt.namelen = name.len() as i32;
+        Ok(self)
+    }
+}
+
+/*
+ * CS_DATE and the day part of CS_DATETIME count days since 1900-01-01
+ */
+fn days_since_epoch(date: &NaiveDate) -> Result<i32> {
+    let epoch = NaiveDate::from_ymd_opt(1900, 1, 1).expect("Invalid epoch");
+    date.signed_duration_since(epoch)
+        .num_days()
+        .try_into()
+        .map_err(|_| Error::from_message("Date out of range"))
+}
+
+/*
+ * CS_TIME and the time part of CS_DATETIME count 1/300 seconds since midnight
+ */
+fn time_ticks(time: &NaiveTime) -> i32 {
+    const MAX_TICKS: i64 = 24 * 60 * 60 * 300 - 1;
+    let seconds = time.num_seconds_from_midnight() as i64;
+    let nanos = (time.nanosecond() % 1_000_000_000) as i64;
+    let ticks = seconds * 300 + (nanos * 3 + 5_000_000) / 10_000_000;
+    ticks.min(MAX_TICKS) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_ticks() {
+        assert_eq!(0, time_ticks(&NaiveTime::from_hms_opt(0, 0, 0).unwrap()));
+        assert_eq!(300, time_ticks(&NaiveTime::from_hms_opt(0, 0, 1).unwrap()));
+        assert_eq!(330, time_ticks(&NaiveTime::from_hms_milli_opt(0, 0, 1, 100).unwrap()));
+        assert_eq!(
+            24 * 60 * 60 * 300 - 1,
+            time_ticks(&NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_output_maxlength() {
+        let param = Param {
+            fmt: CS_DATAFMT {
+                datatype: CS_LONGCHAR_TYPE,
+                maxlength: 300,
+                ..Default::default()
+            },
+            buffer: vec![b'a'; 300],
+            indicator: 0,
+        };
+        assert_eq!(LONG_DATA_LEN as i32, param.output().fmt.maxlength);
+
+        let param = Param {
+            fmt: CS_DATAFMT {
+                datatype: CS_CHAR_TYPE,
+                ..Default::default()
+            },
+            buffer: Vec::new(),
+            indicator: -1,
+        };
+        let param = param.null_type(CS_INT_TYPE).output();
+        assert_eq!(CS_INT_TYPE, param.fmt.datatype);
+        assert_eq!(CS_RETURN, param.fmt.status);
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(0, days_since_epoch(&NaiveDate::from_ymd_opt(1900, 1, 1).unwrap()).unwrap());
+        assert_eq!(31, days_since_epoch(&NaiveDate::from_ymd_opt(1900, 2, 1).unwrap()).unwrap());
+        assert_eq!(-1, days_since_epoch(&NaiveDate::from_ymd_opt(1899, 12, 31).unwrap()).unwrap());
+    }
+}
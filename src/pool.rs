@@ -0,0 +1,569 @@
+use crate::connection::ConnectionBuilder;
+use crate::util::quote_identifier;
+use crate::{Connection, Error, Result};
+use log::warn;
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct PoolBuilder {
+    builder: ConnectionBuilder,
+    max_size: usize,
+    min_size: usize,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    ping: bool,
+    checkout_timeout: Duration,
+}
+
+impl PoolBuilder {
+    fn new(builder: ConnectionBuilder) -> Self {
+        Self {
+            builder,
+            max_size: 10,
+            min_size: 0,
+            idle_timeout: None,
+            max_lifetime: None,
+            ping: false,
+            checkout_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /*
+     * Maximum number of open connections, idle or checked out (default 10)
+     */
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /*
+     * Number of connections opened by build, and kept open regardless of idle_timeout
+     */
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /*
+     * Close connections which stayed idle longer than this
+     */
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /*
+     * Close connections older than this, instead of handing them out
+     */
+    pub fn max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_lifetime = Some(lifetime);
+        self
+    }
+
+    /*
+     * Run "select 1" on checkout, in addition to checking the connection status
+     */
+    pub fn ping(mut self, enabled: bool) -> Self {
+        self.ping = enabled;
+        self
+    }
+
+    /*
+     * How long Pool::get waits for a connection when max_size connections are checked out
+     */
+    pub fn checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Result<Pool> {
+        if self.max_size == 0 || self.min_size > self.max_size {
+            return Err(Error::from_message("Invalid pool size"));
+        }
+
+        let mut idle = VecDeque::with_capacity(self.max_size);
+        for _ in 0..self.min_size {
+            idle.push_back(IdleConnection::new(self.builder.connect()?));
+        }
+
+        Ok(Pool {
+            inner: Arc::new(PoolInner {
+                state: Mutex::new(PoolState {
+                    total: idle.len(),
+                    idle,
+                }),
+                available: Condvar::new(),
+                config: self,
+            }),
+        })
+    }
+}
+
+struct IdleConnection {
+    conn: Connection,
+    created: Instant,
+    last_used: Instant,
+}
+
+impl IdleConnection {
+    fn new(conn: Connection) -> Self {
+        let now = Instant::now();
+        Self {
+            conn,
+            created: now,
+            last_used: now,
+        }
+    }
+}
+
+struct PoolState {
+    idle: VecDeque<IdleConnection>,
+
+    /* idle and checked out connections */
+    total: usize,
+}
+
+struct PoolInner {
+    config: PoolBuilder,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+/*
+ * A pool of connections opened from the same ConnectionBuilder
+ * Clones share the same pool.
+ */
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<PoolInner>,
+}
+
+impl Pool {
+    pub fn builder(builder: ConnectionBuilder) -> PoolBuilder {
+        PoolBuilder::new(builder)
+    }
+
+    /*
+     * Check out a connection, opening a new one if none is idle and max_size is not reached
+     * The connection returns to the pool when the PooledConnection is dropped.
+     */
+    pub fn get(&self) -> Result<PooledConnection> {
+        self.maintain();
+
+        let deadline = Instant::now() + self.inner.config.checkout_timeout;
+        let mut state = self.state();
+        loop {
+            if let Some(idle) = state.idle.pop_back() {
+                if self.is_expired(&idle, state.total) {
+                    drop(state);
+                    drop(idle);
+                    self.release_slot();
+                    state = self.state();
+                    continue;
+                }
+
+                drop(state);
+                let mut conn = idle.conn;
+                if self.validate(&mut conn) {
+                    return Ok(PooledConnection {
+                        pool: self.clone(),
+                        conn: Some(conn),
+                        created: idle.created,
+                    });
+                }
+                drop(conn);
+                self.release_slot();
+                state = self.state();
+                continue;
+            }
+
+            if state.total < self.inner.config.max_size {
+                state.total += 1;
+                drop(state);
+                return match self.inner.config.builder.connect() {
+                    Ok(conn) => Ok(PooledConnection {
+                        pool: self.clone(),
+                        conn: Some(conn),
+                        created: Instant::now(),
+                    }),
+                    Err(e) => {
+                        self.release_slot();
+                        Err(e)
+                    }
+                };
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::from_message("Timed out waiting for a pooled connection"));
+            }
+            state = self
+                .inner
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /*
+     * Number of open connections, idle or checked out
+     */
+    pub fn size(&self) -> usize {
+        self.state().total
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.state().idle.len()
+    }
+
+    fn state(&self) -> MutexGuard<'_, PoolState> {
+        self.inner.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_expired(&self, idle: &IdleConnection, total: usize) -> bool {
+        let config = &self.inner.config;
+        if let Some(max_lifetime) = config.max_lifetime {
+            if idle.created.elapsed() >= max_lifetime {
+                return true;
+            }
+        }
+        if let Some(idle_timeout) = config.idle_timeout {
+            if total > config.min_size && idle.last_used.elapsed() >= idle_timeout {
+                return true;
+            }
+        }
+        false
+    }
+
+    /*
+     * Close the expired idle connections, then open connections until min_size are open
+     */
+    fn maintain(&self) {
+        self.reap();
+        self.fill();
+    }
+
+    /*
+     * Close the expired idle connections, wherever they are in the idle list,
+     * without holding the state lock
+     */
+    fn reap(&self) {
+        let expired: Vec<IdleConnection> = {
+            let mut state = self.state();
+            let mut expired = Vec::new();
+            let mut i = 0;
+            while i < state.idle.len() {
+                if self.is_expired(&state.idle[i], state.total) {
+                    if let Some(idle) = state.idle.remove(i) {
+                        expired.push(idle);
+                    }
+                    state.total -= 1;
+                } else {
+                    i += 1;
+                }
+            }
+            expired
+        };
+        drop(expired);
+        self.inner.available.notify_all();
+    }
+
+    /*
+     * Open connections until min_size are open, without holding the state lock
+     */
+    fn fill(&self) {
+        loop {
+            {
+                let mut state = self.state();
+                if state.total >= self.inner.config.min_size {
+                    break;
+                }
+                state.total += 1;
+            }
+            match self.inner.config.builder.connect() {
+                Ok(conn) => {
+                    self.state().idle.push_front(IdleConnection::new(conn));
+                    self.inner.available.notify_one();
+                },
+                Err(e) => {
+                    warn!("Cannot open pooled connection: {}", e);
+                    self.release_slot();
+                    break;
+                },
+            }
+        }
+    }
+
+    fn validate(&self, conn: &mut Connection) -> bool {
+        if !conn.is_connected() {
+            return false;
+        }
+        if self.inner.config.ping {
+            if let Err(e) = conn.execute("select 1", &[]) {
+                warn!("Pooled connection ping failed: {}", e);
+                return false;
+            }
+        }
+        true
+    }
+
+    /*
+     * Restore the session state and the settings expected by the next user of the connection
+     */
+    fn reset(&self, conn: &mut Connection) -> Result<()> {
+        conn.reset_settings();
+        if conn.trancount()? > 0 {
+            conn.execute("rollback tran", &[])?;
+        }
+        if let Some(database) = self.inner.config.builder.get_database() {
+            conn.execute(format!("use {}", quote_identifier(&database)), &[])?;
+        }
+        Ok(())
+    }
+
+    fn put_back(&self, mut conn: Connection, created: Instant) {
+        /*
+         * A streamed ResultSet, cursor, prepared statement or bulk copy still uses the connection
+         */
+        if conn.lock().handle_count() > 0 {
+            warn!("Pooled connection returned while still in use, closing it");
+            self.discard(conn);
+            return;
+        }
+
+        /* A clone of the connection, or a prepared statement, outlives the PooledConnection */
+        if conn.is_shared() {
+            warn!("Pooled connection returned while still shared, closing it");
+            self.discard(conn);
+            return;
+        }
+
+        if let Some(max_lifetime) = self.inner.config.max_lifetime {
+            if created.elapsed() >= max_lifetime {
+                self.discard(conn);
+                return;
+            }
+        }
+
+        if let Err(e) = self.reset(&mut conn) {
+            warn!("Cannot reset pooled connection, closing it: {}", e);
+            self.discard(conn);
+            return;
+        }
+
+        self.state().idle.push_back(IdleConnection {
+            conn,
+            created,
+            last_used: Instant::now(),
+        });
+        self.inner.available.notify_one();
+
+        /* Missing connections are opened by the next get, not while dropping a PooledConnection */
+        self.reap();
+    }
+
+    /*
+     * Close the server connection, which the ResultSets or clones still holding it can no longer use,
+     * and free its slot
+     */
+    fn discard(&self, mut conn: Connection) {
+        if let Err(e) = conn.close() {
+            warn!("Cannot close pooled connection: {}", e);
+        }
+        self.release_slot();
+    }
+
+    fn release_slot(&self) {
+        self.state().total -= 1;
+        self.inner.available.notify_one();
+    }
+}
+
+/*
+ * A connection checked out from a Pool, returned to it on drop
+ * The connection must not be cloned: a clone still alive when the PooledConnection is dropped
+ * would share the session with the next borrower, so the connection is closed instead.
+ * Buffered ResultSets can outlive it.
+ */
+pub struct PooledConnection {
+    pool: Pool,
+    conn: Option<Connection>,
+    created: Instant,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_ref().expect("Unexpected None value")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn.as_mut().expect("Unexpected None value")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.put_back(conn, self.created);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pool;
+    use crate::tests::builder;
+    use crate::Dialect;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pool() {
+        let pool = Pool::builder(builder().database("master"))
+            .min_size(1)
+            .max_size(2)
+            .ping(true)
+            .checkout_timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        assert_eq!(1, pool.size());
+
+        let mut conn = pool.get().unwrap();
+        conn.execute("use sybsystemprocs", &[]).unwrap();
+        conn.execute("begin tran", &[]).unwrap();
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+
+        /* Session state was reset on return */
+        let mut conn = pool.get().unwrap();
+        assert_eq!(0, conn.trancount().unwrap());
+        assert_eq!("master", conn.db_name().unwrap());
+
+        let conn2 = pool.get().unwrap();
+        assert_eq!(2, pool.size());
+        assert!(pool.get().is_err());
+
+        let pool2 = pool.clone();
+        let t = thread::spawn(move || pool2.get().map(|_| ()));
+        thread::sleep(Duration::from_millis(100));
+        drop(conn2);
+        assert!(t.join().unwrap().is_ok());
+        drop(conn);
+        assert_eq!(2, pool.idle_count());
+    }
+
+    #[test]
+    fn test_pool_in_use() {
+        let pool = Pool::builder(builder()).max_size(1).build().unwrap();
+
+        /* Buffered results do not use the connection any more */
+        let mut conn = pool.get().unwrap();
+        let mut rs = conn.execute("select 1", &[]).unwrap();
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+        assert!(rs.next());
+        drop(rs);
+
+        /* Neither does a cancel handle */
+        let conn = pool.get().unwrap();
+        let handle = conn.cancel_handle();
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+        drop(handle);
+
+        /* Streamed results are still being read: the connection is closed */
+        let mut conn = pool.get().unwrap();
+        conn.set_streaming(true);
+        let mut rs = conn.execute("select name from master..sysobjects", &[]).unwrap();
+        assert!(rs.next());
+        drop(conn);
+        assert_eq!(0, pool.size());
+
+        /* The server connection was closed */
+        while rs.next() {}
+        assert!(rs.error().is_some());
+    }
+
+    #[test]
+    fn test_pool_settings() {
+        let pool = Pool::builder(builder()).max_size(1).build().unwrap();
+
+        let mut conn = pool.get().unwrap();
+        let dialect = conn.dialect();
+        let other = if dialect == Dialect::Mssql { Dialect::Sybase } else { Dialect::Mssql };
+        conn.set_streaming(true);
+        conn.set_server_side_params(true);
+        conn.set_dialect(other);
+        conn.set_message_callback(Box::new(|_| false));
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+
+        let mut conn = pool.get().unwrap();
+        assert_eq!(dialect, conn.dialect());
+        let rs = conn.execute("print 'hello'", &[]).unwrap();
+        assert!(!rs.is_streaming());
+        assert_eq!(1, rs.messages().len());
+    }
+
+    #[test]
+    fn test_pool_clone() {
+        let pool = Pool::builder(builder()).max_size(1).build().unwrap();
+
+        /* A clone which outlives the PooledConnection closes the connection */
+        let conn = pool.get().unwrap();
+        let mut clone = (*conn).clone();
+        drop(conn);
+        assert_eq!(0, pool.size());
+        assert!(!clone.is_connected());
+        drop(clone);
+
+        let conn = pool.get().unwrap();
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+    }
+
+    #[test]
+    fn test_pool_lifetime() {
+        let pool = Pool::builder(builder())
+            .max_size(1)
+            .max_lifetime(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let conn = pool.get().unwrap();
+        drop(conn);
+        assert_eq!(1, pool.idle_count());
+        thread::sleep(Duration::from_millis(200));
+        let mut conn = pool.get().unwrap();
+        assert_eq!(0, pool.idle_count());
+        assert!(conn.is_connected());
+    }
+
+    #[test]
+    fn test_pool_idle_timeout() {
+        let pool = Pool::builder(builder())
+            .max_size(3)
+            .min_size(1)
+            .idle_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let conn1 = pool.get().unwrap();
+        let conn2 = pool.get().unwrap();
+        let conn3 = pool.get().unwrap();
+        drop(conn1);
+        drop(conn2);
+        thread::sleep(Duration::from_millis(200));
+
+        /* Both expired connections are closed, not only the most recently used one */
+        drop(conn3);
+        assert_eq!(1, pool.size());
+        assert_eq!(1, pool.idle_count());
+    }
+}
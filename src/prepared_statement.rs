@@ -0,0 +1,161 @@
+use crate::column_info::ColumnInfo;
+use crate::command::Command;
+use crate::connection::ReadState;
+use crate::param::Param;
+use crate::result_set::{Column, SybResult};
+use crate::to_sql::ToSql;
+use crate::{Connection, Error, ResultSet, Result};
+use freetds_sys::*;
+use log::warn;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/*
+ * A statement prepared on the server with ct_dynamic, obtained with Connection::prepare
+ * Placeholders are written as ?, and every execution binds the parameters with ct_param,
+ * so the statement is only parsed once by the server.
+ * The statement is deallocated when dropped.
+ */
+pub struct PreparedStatement {
+    conn: Connection,
+    id: String,
+    text: String,
+    params: Vec<Column>,
+    columns: Vec<Column>,
+}
+
+impl PreparedStatement {
+    pub(crate) fn new(conn: &Connection, text: &str) -> Result<Self> {
+        let id = format!("freetds_dyn{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        Self::run(conn, CS_PREPARE, &id, Some(text))?;
+
+        let mut st = Self {
+            /* Counted as a clone: the statement belongs to the server session, see Connection::is_shared */
+            conn: conn.clone(),
+            id,
+            text: String::from(text),
+            params: Vec::new(),
+            columns: Vec::new(),
+        };
+        st.params = Self::run(conn, CS_DESCRIBE_INPUT, &st.id, None)?;
+        st.columns = Self::run(conn, CS_DESCRIBE_OUTPUT, &st.id, None)?;
+        Ok(st)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+
+    /*
+     * Input parameters, as described by the server
+     */
+    pub fn params(&self) -> Vec<ColumnInfo> {
+        self.params.iter().map(ColumnInfo::from).collect()
+    }
+
+    /*
+     * Columns of the rows returned by the statement, empty if it does not return rows
+     */
+    pub fn columns(&self) -> Vec<ColumnInfo> {
+        self.columns.iter().map(ColumnInfo::from).collect()
+    }
+
+    pub fn execute(&mut self, params: &[&dyn ToSql]) -> Result<ResultSet> {
+        if params.len() != self.params.len() {
+            return Err(Error::from_message(format!(
+                "Invalid parameter count: the statement has {} parameters, got {}",
+                self.params.len(),
+                params.len()
+            )));
+        }
+
+        let mut command = Command::new(self.conn.share());
+        command.dynamic(CS_EXECUTE, &self.id, None)?;
+        for (param, described) in params.iter().zip(self.params.iter()) {
+            let value = param
+                .to_value()
+                .ok_or_else(|| Error::from_message("Parameter type cannot be bound server-side"))?;
+
+            /* NULL values are sent with the described type, the server may not convert them from char */
+            let mut param = Param::new(&mut self.conn, &value)?.null_type(described.described.datatype);
+            command.param(&mut param)?;
+        }
+        command.send()?;
+        self.conn.process_results(command, true)
+    }
+
+    /*
+     * Send a dynamic command and read its results, returns the described columns if any
+     */
+    fn run(conn: &Connection, type_: i32, id: &str, text: Option<&str>) -> Result<Vec<Column>> {
+        let mut conn = conn.share();
+        let mut command = Command::new(conn.share());
+        command.dynamic(type_, id, text)?;
+        command.send()?;
+
+        let mut state = ReadState {
+            streaming: false,
+            unbound: false,
+            fail_on_status: true,
+            failed: false,
+            errors: Vec::new(),
+        };
+        let mut columns = Vec::new();
+        while let Some((result, _)) = conn.read_result(&mut command, &mut state)? {
+            if let SybResult::Rows(rows) = result {
+                columns = rows.columns;
+            }
+        }
+
+        if state.failed {
+            return Err(Connection::get_single_error(&state.errors)
+                .unwrap_or_else(|| Error::from_message("ct_dynamic command failed")));
+        }
+        Ok(columns)
+    }
+}
+
+impl Drop for PreparedStatement {
+    fn drop(&mut self) {
+        if let Err(e) = Self::run(&self.conn, CS_DEALLOC, &self.id, None) {
+            warn!("Cannot deallocate prepared statement {}: {}", self.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{connect, scratch_table};
+    use crate::DataType;
+
+    #[test]
+    fn test_prepared_statement() {
+        let mut conn = connect();
+        scratch_table(&mut conn, "freetds_prepared", "id int, name varchar(32) null");
+
+        let mut insert = conn.prepare("insert into freetds_prepared values(?, ?)").unwrap();
+        assert_eq!(2, insert.param_count());
+        assert_eq!(DataType::Int, insert.params()[0].datatype());
+        assert!(insert.columns().is_empty());
+        for i in 0..10 {
+            insert.execute(&[&i, &format!("row {}", i)]).unwrap();
+        }
+        insert.execute(&[&10, &None::<String>]).unwrap();
+        assert!(insert.execute(&[&1]).is_err());
+        drop(insert);
+
+        let mut select = conn.prepare("select name from freetds_prepared where id = ?").unwrap();
+        assert_eq!("name", select.columns()[0].name());
+        let mut rs = select.execute(&[&7]).unwrap();
+        assert!(rs.next());
+        assert_eq!(Some(String::from("row 7")), rs.get_string(0).unwrap());
+        assert!(!rs.next());
+
+        assert!(conn.prepare("select * from no_such_table").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{DataType, Value};
+
+/*
+ * A stored procedure parameter, for Connection::call_procedure
+ */
+#[derive(Debug, Clone)]
+pub struct ProcParam {
+    pub(crate) name: String,
+    pub(crate) value: Value,
+    pub(crate) output: bool,
+
+    /* Datatype a NULL value is sent as, see null_output */
+    pub(crate) null_type: Option<DataType>,
+}
+
+impl ProcParam {
+    pub fn input(name: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: Self::param_name(name),
+            value: value.into(),
+            output: false,
+            null_type: None,
+        }
+    }
+
+    /*
+     * The initial value determines the parameter's datatype,
+     * its returned value can be read with ResultSet::get_output
+     * A NULL initial value is sent as char, use null_output for other datatypes.
+     */
+    pub fn output(name: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: Self::param_name(name),
+            value: value.into(),
+            output: true,
+            null_type: None,
+        }
+    }
+
+    /*
+     * Output parameter with a NULL initial value of the given datatype
+     * Numeric and decimal values are returned without their fractional part:
+     * use output with a value of the right scale instead.
+     */
+    pub fn null_output(name: &str, datatype: DataType) -> Self {
+        Self {
+            name: Self::param_name(name),
+            value: Value::Null,
+            output: true,
+            null_type: Some(datatype),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    pub fn is_output(&self) -> bool {
+        self.output
+    }
+
+    fn param_name(name: &str) -> String {
+        if name.starts_with('@') {
+            name.to_string()
+        } else {
+            format!("@{}", name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcParam;
+    use crate::{DataType, Value};
+
+    #[test]
+    fn test_param_name() {
+        assert_eq!("@a", ProcParam::input("a", 1).name());
+        assert_eq!("@b", ProcParam::output("@b", 1).name());
+        assert!(ProcParam::output("@b", 1).is_output());
+        assert_eq!(&Value::I32(1), ProcParam::input("a", 1).value());
+        assert_eq!(&Value::Null, ProcParam::null_output("c", DataType::Int).value());
+        assert!(ProcParam::null_output("c", DataType::Int).is_output());
+    }
+}
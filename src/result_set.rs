@@ -1,20 +1,99 @@
 #![allow(clippy::expect_fun_call)]
 
+use crate::cancel::CancelHandle;
+use crate::column_info::ColumnInfo;
+use crate::command::Command;
+use crate::connection::{Bind, ReadState};
+use crate::de::RowDeserializer;
+use crate::from_row::FromRow;
+use crate::from_sql::FromSql;
 use crate::{column_id::ColumnId, Connection, Error, Result, Value};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use freetds_sys::*;
 use rust_decimal::Decimal;
-use std::{mem, ops::Deref, rc::Rc};
+use log::warn;
+use serde::de::DeserializeOwned;
+use std::cell::Cell;
+use std::{mem, ops::Deref, sync::Arc};
+
+/*
+ * The CS_DATAFMT fields kept with a column, without the name and the locale pointer
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct DataFormat {
+    pub datatype: i32,
+    pub format: i32,
+    pub maxlength: i32,
+    pub scale: i32,
+    pub precision: i32,
+    pub status: i32,
+    pub count: i32,
+    pub usertype: i32,
+}
+
+impl DataFormat {
+    pub fn to_datafmt(self) -> CS_DATAFMT {
+        CS_DATAFMT {
+            datatype: self.datatype,
+            format: self.format,
+            maxlength: self.maxlength,
+            scale: self.scale,
+            precision: self.precision,
+            status: self.status,
+            count: self.count,
+            usertype: self.usertype,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&CS_DATAFMT> for DataFormat {
+    fn from(fmt: &CS_DATAFMT) -> Self {
+        Self {
+            datatype: fmt.datatype,
+            format: fmt.format,
+            maxlength: fmt.maxlength,
+            scale: fmt.scale,
+            precision: fmt.precision,
+            status: fmt.status,
+            count: fmt.count,
+            usertype: fmt.usertype,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct Column {
     pub(crate) name: String,
-    pub(crate) fmt: CS_DATAFMT,
+    pub(crate) fmt: DataFormat,
+
+    /* fmt as returned by ct_describe, before being adjusted for binding */
+    pub(crate) described: DataFormat,
+}
+
+impl Column {
+    /*
+     * Column as returned by ct_describe, fmt is then adjusted by the caller before binding
+     */
+    pub(crate) fn new(described: CS_DATAFMT) -> Self {
+        let name: Vec<u8> = described
+            .name
+            .iter()
+            .take(described.namelen as usize)
+            .map(|c| *c as u8)
+            .collect();
+        let described = DataFormat::from(&described);
+        Self {
+            name: String::from(String::from_utf8_lossy(&name)),
+            fmt: described,
+            described,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Row {
-    pub(crate) buffers: Vec<Option<Rc<Vec<u8>>>>,
+    pub(crate) buffers: Vec<Option<Arc<Vec<u8>>>>,
 }
 
 #[derive(Debug)]
@@ -40,6 +119,46 @@ pub enum ResultType {
     Rows,
     Status,
     UpdateCount,
+    Params,
+    Compute,
+}
+
+/*
+ * Aggregate operator of a compute column
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeOp {
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+    Other(i32),
+}
+
+impl From<i32> for ComputeOp {
+    fn from(op: i32) -> Self {
+        match op {
+            CS_OPT_SUM => Self::Sum,
+            CS_OPT_AVG => Self::Avg,
+            CS_OPT_COUNT => Self::Count,
+            CS_OPT_MIN => Self::Min,
+            CS_OPT_MAX => Self::Max,
+            _ => Self::Other(op),
+        }
+    }
+}
+
+/*
+ * Rows of a compute clause, with the aggregate operator of each column
+ * and the select list column (1-based) it applies to
+ */
+#[derive(Debug)]
+pub(crate) struct ComputeRows {
+    pub id: i32,
+    pub ops: Vec<ComputeOp>,
+    pub colids: Vec<i32>,
+    pub rows: Rows,
 }
 
 #[derive(Debug)]
@@ -47,6 +166,31 @@ pub(crate) enum SybResult {
     Rows(Rows),
     Status(i32),
     UpdateCount(u64),
+    Params(Rows),
+    Compute(ComputeRows),
+}
+
+impl SybResult {
+    /*
+     * Rows which can be read with ResultSet::next and the getters
+     */
+    fn rows(&self) -> Option<&Rows> {
+        match self {
+            SybResult::Rows(rows) | SybResult::Compute(ComputeRows { rows, .. }) => Some(rows),
+            _ => None,
+        }
+    }
+}
+
+/*
+ * Streaming mode: the command whose results are still being read,
+ * and the binds of the row result currently being fetched.
+ * Dropping the command cancels its remaining results.
+ */
+pub(crate) struct Stream {
+    pub command: Command,
+    pub binds: Option<Vec<Bind>>,
+    pub state: ReadState,
 }
 
 pub struct ResultSet {
@@ -56,6 +200,13 @@ pub struct ResultSet {
     pub(crate) pos: Option<usize>,
     pub(crate) results: Vec<SybResult>,
     pub(crate) messages: Vec<Error>,
+
+    /* streaming mode only */
+    pub(crate) stream: Option<Stream>,
+    stream_error: Option<Error>,
+
+    /* whether stream_error was returned to the caller, it is logged on drop otherwise */
+    stream_error_reported: Cell<bool>,
 }
 
 impl ResultSet {
@@ -63,19 +214,23 @@ impl ResultSet {
         conn: Connection,
         results: Vec<SybResult>,
         messages: Vec<Error>,
+        stream: Option<Stream>,
     ) -> Self {
         Self {
             conn,
             pos: None,
             results,
             messages,
+            stream,
+            stream_error: None,
+            stream_error_reported: Cell::new(false),
         }
     }
 
     pub fn next_results(&mut self) -> bool {
         match self.pos {
             None => {
-                if self.results.is_empty() {
+                if self.results.is_empty() && !self.read_next_result() {
                     return false;
                 } else {
                     self.pos = Some(0);
@@ -86,7 +241,122 @@ impl ResultSet {
             }
         }
 
-        self.pos.expect("Unexpected None value") < self.results.len()
+        let pos = self.pos.expect("Unexpected None value");
+        if pos >= self.results.len() {
+            self.read_next_result();
+        }
+        pos < self.results.len()
+    }
+
+    /*
+     * Handle to cancel the query from another thread, while rows are being streamed
+     */
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.conn.cancel_handle()
+    }
+
+    /*
+     * Returns true while results are still being read from the server (streaming mode)
+     */
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /*
+     * Streaming mode: read the next result from the server and append it to self.results.
+     * Unread rows of the result being streamed are discarded.
+     * Returns false when there are no more results.
+     */
+    fn read_next_result(&mut self) -> bool {
+        let stream = match self.stream.as_mut() {
+            None => return false,
+            Some(stream) => stream,
+        };
+
+        if stream.binds.take().is_some() {
+            if let Err(e) = stream.command.cancel(CS_CANCEL_CURRENT) {
+                self.stream_failed(e);
+                return false;
+            }
+        }
+
+        let read = self.conn.read_result(&mut stream.command, &mut stream.state);
+        self.messages.append(&mut stream.state.errors);
+        let failed = mem::replace(&mut stream.state.failed, false);
+        let result = match read {
+            Ok(Some((result, binds))) => {
+                stream.binds = binds;
+                self.results.push(result);
+                true
+            },
+            Ok(None) => {
+                self.stream = None;
+                false
+            },
+            Err(e) => {
+                self.stream_failed(e);
+                return false;
+            },
+        };
+
+        if failed && self.stream_error.is_none() {
+            self.stream_error = Some(
+                Connection::get_single_error(&self.messages)
+                    .unwrap_or_else(|| Error::from_message("Query execution resulted in error")),
+            );
+        }
+        result
+    }
+
+    /*
+     * Streaming mode: fetch the next row of the result being streamed
+     */
+    fn next_streamed_row(&mut self, result_index: usize) -> bool {
+        let Stream { command, binds, .. } = self.stream.as_mut().expect("Unexpected None value");
+        let rows = match self.results.get_mut(result_index) {
+            Some(SybResult::Rows(rows)) => rows,
+            _ => return false,
+        };
+
+        match Connection::fetch_row(command, &rows.columns, binds.as_ref().expect("Unexpected None value")) {
+            Ok(Some(row)) => {
+                rows.rows = vec![row];
+                rows.pos = Some(0);
+                true
+            },
+            Ok(None) => {
+                rows.rows.clear();
+                rows.pos = Some(0);
+                *binds = None;
+                false
+            },
+            Err(e) => {
+                rows.rows.clear();
+                rows.pos = Some(0);
+                self.stream_failed(e);
+                false
+            },
+        }
+    }
+
+    /*
+     * Streaming mode: record the error and stop reading results
+     */
+    fn stream_failed(&mut self, error: Error) {
+        if self.stream_error.is_none() {
+            self.stream_error = Some(error);
+        }
+        self.stream = None;
+    }
+
+    /*
+     * Returns true if results[index] is the row result currently being streamed
+     */
+    fn is_streamed_rows(&self, index: usize) -> bool {
+        match &self.stream {
+            Some(stream) => stream.binds.is_some() && index + 1 == self.results.len(),
+            None => false,
+        }
     }
 
     pub fn next_results_of_type(&mut self, type_: ResultType) -> bool {
@@ -111,49 +381,53 @@ impl ResultSet {
                     SybResult::Rows(_) => ResultType::Rows,
                     SybResult::Status(_) => ResultType::Status,
                     SybResult::UpdateCount(_) => ResultType::UpdateCount,
+                    SybResult::Params(_) => ResultType::Params,
+                    SybResult::Compute(_) => ResultType::Compute,
                 }
             }
         }
     }
 
     /*
-     * Seek self.result_index to next item in results which contains rows
-     * and return the new result
-     * If no more row result, return None and set result_index out of range value
+     * Seek self.pos to next item in results which matches,
+     * reading more results from the server in streaming mode
+     * If no result matches, return None and set pos out of range value
      */
-    fn next_row_result(&mut self) -> Option<usize> {
-        for (i, r) in self.results.iter().skip(self.pos.unwrap_or(0)).enumerate() {
-            if let SybResult::Rows(_) = r {
-                self.pos = Some(i);
-                return Some(i);
+    fn seek_result(&mut self, matches: fn(&SybResult) -> bool) -> Option<usize> {
+        let mut i = self.pos.unwrap_or(0);
+        loop {
+            while i < self.results.len() {
+                if matches(&self.results[i]) {
+                    self.pos = Some(i);
+                    return Some(i);
+                }
+                i += 1;
+            }
+            if !self.read_next_result() {
+                break;
             }
         }
         self.pos = Some(self.results.len());
-        return None;
+        None
+    }
+
+    fn next_row_result(&mut self) -> Option<usize> {
+        self.seek_result(|r| matches!(r, SybResult::Rows(_)))
     }
 
     fn next_status_result(&mut self) -> Option<usize> {
-        for (i, r) in self.results.iter().skip(self.pos.unwrap_or(0)).enumerate() {
-            if let SybResult::Status(_) = r {
-                self.pos = Some(i);
-                return Some(i);
-            }
-        }
-        self.pos = Some(self.results.len());
-        return None;
+        self.seek_result(|r| matches!(r, SybResult::Status(_)))
     }
 
     fn next_update_count_result(&mut self) -> Option<usize> {
-        for (i, r) in self.results.iter().skip(self.pos.unwrap_or(0)).enumerate() {
-            if let SybResult::UpdateCount(_) = r {
-                self.pos = Some(i);
-                return Some(i);
-            }
-        }
-        self.pos = Some(self.results.len());
-        return None;
+        self.seek_result(|r| matches!(r, SybResult::UpdateCount(_)))
     }
 
+    /*
+     * Move to the next row of the current row result, returns false when there are no more rows
+     * In streaming mode, false is also returned when reading rows failed: check error()
+     * once the loop ends, or use try_next.
+     */
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> bool {
         /*
@@ -169,10 +443,14 @@ impl ResultSet {
          * We can assume here that result_index is not None
          */
         let result_index = self.pos.expect("Unexpected None value");
+        if self.is_streamed_rows(result_index) {
+            return self.next_streamed_row(result_index);
+        }
+
         match self.results.get_mut(result_index) {
             Some(result) => {
                 match result {
-                    SybResult::Rows(rows) => {
+                    SybResult::Rows(rows) | SybResult::Compute(ComputeRows { rows, .. }) => {
                         if rows.rows.is_empty() {
                             rows.pos = Some(rows.rows.len());
                             return false;
@@ -195,6 +473,35 @@ impl ResultSet {
         }
     }
 
+    /*
+     * Like next, but returns the error which ended the rows in streaming mode
+     */
+    pub fn try_next(&mut self) -> Result<bool> {
+        if self.next() {
+            return Ok(true);
+        }
+        match self.stream_error.as_ref() {
+            Some(error) => {
+                self.stream_error_reported.set(true);
+                Err(error.clone())
+            },
+            None => Ok(false),
+        }
+    }
+
+    /*
+     * The error which ended streaming if any, otherwise an error built from desc
+     */
+    fn state_error(&self, desc: &str) -> Error {
+        match self.stream_error.as_ref() {
+            Some(error) => {
+                self.stream_error_reported.set(true);
+                error.clone()
+            },
+            None => Error::from_message(desc),
+        }
+    }
+
     /*
      * Returns true if current results contains SybResult::rows
      */
@@ -241,7 +548,7 @@ impl ResultSet {
         let results = self.results.get(pos);
         if let Some(results) = results {
             match results {
-                SybResult::Rows(rows) => {
+                SybResult::Rows(rows) | SybResult::Compute(ComputeRows { rows, .. }) => {
                     Ok(rows.columns.len())
                 },
                 _ => {
@@ -253,6 +560,66 @@ impl ResultSet {
         }
     }
 
+    /*
+     * Description of a column of the current row result, available before reading any row
+     */
+    pub fn column_info(&mut self, index: usize) -> Result<ColumnInfo> {
+        self.columns()?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| Error::from_message("Invalid column index"))
+    }
+
+    /*
+     * Seek to next row results if first call, then describe its columns
+     */
+    pub fn columns(&mut self) -> Result<Vec<ColumnInfo>> {
+        if self.pos.is_none() && self.next_row_result().is_none() {
+            return Err(Error::from_message("Query did not return rows"));
+        }
+        match self.results.get(self.pos.expect("Unexpected None value")).map(SybResult::rows) {
+            Some(Some(rows)) => Ok(rows.columns.iter().map(ColumnInfo::from).collect()),
+            Some(None) => Err(Error::from_message("Current results do not contain rows")),
+            None => Err(Error::from_message("No more results")),
+        }
+    }
+
+    /*
+     * Id of the compute clause which produced the current compute result
+     */
+    pub fn compute_id(&self) -> Result<i32> {
+        Ok(self.current_compute()?.id)
+    }
+
+    /*
+     * Aggregate operator of a column of the current compute result
+     */
+    pub fn compute_op(&self, index: usize) -> Result<ComputeOp> {
+        self.current_compute()?
+            .ops
+            .get(index)
+            .copied()
+            .ok_or_else(|| Error::from_message("Invalid column index"))
+    }
+
+    /*
+     * Select list column (1-based) aggregated by a column of the current compute result
+     */
+    pub fn compute_column(&self, index: usize) -> Result<i32> {
+        self.current_compute()?
+            .colids
+            .get(index)
+            .copied()
+            .ok_or_else(|| Error::from_message("Invalid column index"))
+    }
+
+    fn current_compute(&self) -> Result<&ComputeRows> {
+        match self.pos.and_then(|pos| self.results.get(pos)) {
+            Some(SybResult::Compute(compute)) => Ok(compute),
+            _ => Err(Error::from_message("ResultSet does not currently point to a compute result")),
+        }
+    }
+
     pub fn column_name(&mut self, index: usize) -> Result<String> {
         if self.pos.is_none() {
             if self.next_row_result().is_none() {
@@ -263,7 +630,7 @@ impl ResultSet {
         let results = self.results.get_mut(pos);
         if let Some(results) = results {
             match results {
-                SybResult::Rows(rows) => {
+                SybResult::Rows(rows) | SybResult::Compute(ComputeRows { rows, .. }) => {
                     if let Some(column) = rows.columns.get(index) {
                         Ok(column.name.clone())
                     } else {
@@ -282,17 +649,17 @@ impl ResultSet {
     fn get_buffer(
         &self,
         col: impl Into<ColumnId>,
-    ) -> Result<(CS_DATAFMT, Option<Rc<Vec<u8>>>)> {
+    ) -> Result<(CS_DATAFMT, Option<Arc<Vec<u8>>>)> {
         if self.pos.is_none() {
-            return Err(Error::from_message("Invalid state"));
+            return Err(self.state_error("Invalid state"));
         }
 
         let pos = self.pos.expect("Unexpected None value");
         if pos >= self.results.len() {
-            return Err(Error::from_message("ResultSet exhausted"));
+            return Err(self.state_error("ResultSet exhausted"));
         }
 
-        if let Some(SybResult::Rows(rows)) = self.results.get(pos) {
+        if let Some(rows) = self.results.get(pos).and_then(SybResult::rows) {
             let col: usize = match Into::<ColumnId>::into(col) {
                 ColumnId::I32(i) => match i.try_into() {
                     Ok(i) => i,
@@ -316,19 +683,18 @@ impl ResultSet {
             }
 
             let column = &rows.columns[col];
-            let row = if let Some(row) = rows.pos {
-                &rows.rows[row]
-            } else {
-                return Err(Error::from_message("Invalid state"));
+            let row = match rows.pos.and_then(|row| rows.rows.get(row)) {
+                Some(row) => row,
+                None => return Err(self.state_error("Invalid state")),
             };
 
             let buffer = &row.buffers[col];
             match buffer {
-                None => Ok((column.fmt, None)),
-                Some(buffer) => Ok((column.fmt, Some(Rc::clone(buffer)))),
+                None => Ok((column.fmt.to_datafmt(), None)),
+                Some(buffer) => Ok((column.fmt.to_datafmt(), Some(Arc::clone(buffer)))),
             }
         } else {
-            return Err(Error::from_message("Invalid state"));
+            return Err(self.state_error("Invalid state"));
         }
     }
 
@@ -356,152 +722,58 @@ impl ResultSet {
         }
     }
 
+    /*
+     * Whether the current row result has a column with this name
+     */
+    pub fn has_column(&self, name: &str) -> bool {
+        self.pos
+            .and_then(|pos| self.results.get(pos))
+            .and_then(SybResult::rows)
+            .is_some_and(|rows| rows.columns.iter().any(|column| column.name == name))
+    }
+
+    /*
+     * Deserialize the current row into any type implementing serde's Deserialize
+     */
+    pub fn deserialize<T: DeserializeOwned>(&mut self) -> Result<T> {
+        T::deserialize(RowDeserializer::new(self)?)
+    }
+
+    /*
+     * Read the remaining rows of the current row result
+     */
+    pub fn collect_as<T: FromRow>(&mut self) -> Result<Vec<T>> {
+        let mut rows = Vec::new();
+        while self.next() {
+            rows.push(T::from_row(self)?);
+        }
+        if let Some(error) = self.stream_error.take() {
+            return Err(error);
+        }
+        Ok(rows)
+    }
+
+    /*
+     * Read a column of the current row as any type implementing FromSql
+     * Null values are an error, unless T is an Option.
+     */
+    pub fn get<T: FromSql>(&mut self, col: impl Into<ColumnId>) -> Result<T> {
+        let col = col.into();
+        T::from_sql(self, col.clone())?.ok_or_else(|| {
+            let name = match col {
+                ColumnId::I32(i) => i.to_string(),
+                ColumnId::String(s) => s,
+            };
+            Error::from_message(format!("Unexpected null value in column {}", name))
+        })
+    }
+
     pub fn get_value(&mut self, col: impl Into<ColumnId>) -> Result<Value> {
         let (fmt, buffer) = self.get_buffer(col)?;
 
         match buffer {
             None => Ok(Value::Null),
-            Some(buffer) => match fmt.datatype {
-                CS_BINARY_TYPE | CS_IMAGE_TYPE => {
-                    let buf: &Vec<u8> = &buffer;
-                    Ok(Value::from(buf))
-                }
-                CS_CHAR_TYPE | CS_TEXT_TYPE => Ok(Value::from(
-                    String::from_utf8_lossy(&buffer).to_string(),
-                )),
-                CS_UNICHAR_TYPE => {
-                    let dstfmt = CS_DATAFMT {
-                        datatype: CS_CHAR_TYPE,
-                        maxlength: buffer.len() as i32,
-                        format: CS_FMT_UNUSED as i32,
-                        count: 1,
-                        ..Default::default()
-                    };
-
-                    let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
-                    let dstlen = self
-                        .conn
-                        .convert(&fmt, &buffer, &dstfmt, &mut dstdata)?;
-                    Ok(Value::from(
-                        String::from_utf8_lossy(&dstdata.as_slice()[0..dstlen]).to_string(),
-                    ))
-                }
-                CS_DATE_TYPE | CS_TIME_TYPE | CS_DATETIME_TYPE | CS_DATETIME4_TYPE => {
-                    let datatype = fmt.datatype;
-                    let daterec = self.convert_date(&fmt, &buffer)?;
-                    Ok(match datatype {
-                        CS_DATE_TYPE => Value::from(
-                            NaiveDate::from_ymd_opt(
-                                daterec.dateyear,
-                                (daterec.datemonth + 1) as u32,
-                                daterec.datedmonth as u32,
-                            )
-                            .ok_or_else(|| Error::from_message("Invalid date"))?,
-                        ),
-                        CS_TIME_TYPE => Value::from(
-                            NaiveTime::from_hms_milli_opt(
-                                daterec.datehour as u32,
-                                daterec.dateminute as u32,
-                                daterec.datesecond as u32,
-                                daterec.datemsecond as u32,
-                            )
-                            .ok_or_else(|| Error::from_message("Invalid time"))?,
-                        ),
-                        CS_DATETIME_TYPE | CS_DATETIME4_TYPE => Value::from(
-                            NaiveDate::from_ymd_opt(
-                                daterec.dateyear,
-                                (daterec.datemonth + 1) as u32,
-                                daterec.datedmonth as u32,
-                            )
-                            .ok_or_else(|| Error::from_message("Invalid date"))?
-                            .and_hms_milli_opt(
-                                daterec.datehour as u32,
-                                daterec.dateminute as u32,
-                                daterec.datesecond as u32,
-                                daterec.datemsecond as u32,
-                            )
-                            .ok_or_else(|| Error::from_message("Invalid date"))?,
-                        ),
-                        _ => panic!("Invalid code path"),
-                    })
-                }
-                CS_INT_TYPE => unsafe {
-                    assert_eq!(buffer.len(), mem::size_of::<i32>());
-                    let ptr: *const i32 = mem::transmute(buffer.as_ptr());
-                    Ok(Value::from(*ptr))
-                },
-                CS_BIT_TYPE | CS_TINYINT_TYPE | CS_SMALLINT_TYPE => {
-                    let dstfmt = CS_DATAFMT {
-                        datatype: CS_INT_TYPE,
-                        maxlength: mem::size_of::<i32>() as i32,
-                        format: CS_FMT_UNUSED as i32,
-                        count: 1,
-                        ..Default::default()
-                    };
-
-                    let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
-                    let dstlen = self
-                        .conn
-                        .convert(&fmt, &buffer, &dstfmt, &mut dstdata)?;
-                    assert_eq!(dstlen, mem::size_of::<i32>());
-                    unsafe {
-                        let ptr: *const i32 = mem::transmute(buffer.as_ptr());
-                        Ok(Value::from(*ptr))
-                    }
-                }
-                CS_MONEY_TYPE | CS_MONEY4_TYPE | CS_DECIMAL_TYPE | CS_NUMERIC_TYPE => {
-                    if fmt.precision == CS_DEF_PREC && fmt.scale == 0 {
-                        let dstfmt = CS_DATAFMT {
-                            datatype: CS_BIGINT_TYPE,
-                            maxlength: mem::size_of::<i64>() as i32,
-                            format: CS_FMT_UNUSED as i32,
-                            count: 1,
-                            ..Default::default()
-                        };
-
-                        let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
-                        let dstlen =
-                            self.conn
-                                .convert(&fmt, &buffer, &dstfmt, &mut dstdata)?;
-                        assert_eq!(dstlen, mem::size_of::<i64>());
-                        unsafe {
-                            let ptr: *const i64 = mem::transmute(dstdata.as_ptr());
-                            Ok(Value::from(*ptr))
-                        }
-                    } else {
-                        let dstfmt = CS_DATAFMT {
-                            datatype: CS_CHAR_TYPE,
-                            maxlength: 1024,
-                            format: CS_FMT_UNUSED as i32,
-                            precision: CS_SRC_VALUE,
-                            scale: CS_SRC_VALUE,
-                            count: 1,
-                            ..Default::default()
-                        };
-
-                        let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
-                        let dstlen =
-                            self.conn
-                                .convert(&fmt, &buffer, &dstfmt, &mut dstdata)?;
-                        let s = String::from_utf8_lossy(&dstdata.as_slice()[0..dstlen]).to_string();
-                        Ok(Value::from(
-                            Decimal::from_str_exact(&s)
-                                .map_err(|_| Error::from_message("Invalid decimal"))?,
-                        ))
-                    }
-                }
-                CS_REAL_TYPE => unsafe {
-                    assert_eq!(buffer.len(), mem::size_of::<f32>());
-                    let ptr: *const f32 = mem::transmute(buffer.as_ptr());
-                    Ok(Value::from(Into::<f64>::into(*ptr)))
-                },
-                CS_FLOAT_TYPE => unsafe {
-                    assert_eq!(buffer.len(), mem::size_of::<f64>());
-                    let ptr: *const f64 = mem::transmute(buffer.as_ptr());
-                    Ok(Value::from(*ptr))
-                },
-                _ => Err(Error::from_message("Unsupported datatype")),
-            },
+            Some(buffer) => buffer_to_value(&mut self.conn, &fmt, &buffer),
         }
     }
 
@@ -666,9 +938,7 @@ impl ResultSet {
             None => Ok(None),
             Some(buffer) => match fmt.datatype {
                 CS_BINARY_TYPE | CS_IMAGE_TYPE => Ok(Some(buffer.deref().clone())),
-                CS_VARBINARY_TYPE => {
-                    todo!();
-                }
+                /* CS_VARBINARY_TYPE buffers start with the length, the library strips it */
                 _ => {
                     let dstfmt = CS_DATAFMT {
                         datatype: CS_BINARY_TYPE,
@@ -719,37 +989,11 @@ impl ResultSet {
         }
     }
 
-    fn convert_date(&mut self, fmt: &CS_DATAFMT, buffer: &[u8]) -> Result<CS_DATEREC> {
-        match fmt.datatype {
-            CS_DATE_TYPE => unsafe {
-                assert!(buffer.len() == mem::size_of::<CS_DATE>());
-                let buf: *const CS_DATE = mem::transmute(buffer.as_ptr());
-                Ok(self.conn.crack_date(*buf)?)
-            },
-            CS_TIME_TYPE => unsafe {
-                assert!(buffer.len() == mem::size_of::<CS_TIME>());
-                let buf: *const CS_TIME = mem::transmute(buffer.as_ptr());
-                Ok(self.conn.crack_time(*buf)?)
-            },
-            CS_DATETIME_TYPE => unsafe {
-                assert!(buffer.len() == mem::size_of::<CS_DATETIME>());
-                let buf: *const CS_DATETIME = mem::transmute(buffer.as_ptr());
-                Ok(self.conn.crack_datetime(*buf)?)
-            },
-            CS_DATETIME4_TYPE => unsafe {
-                assert!(buffer.len() == mem::size_of::<CS_DATETIME4>());
-                let buf: *const CS_DATETIME4 = mem::transmute(buffer.as_ptr());
-                Ok(self.conn.crack_smalldatetime(*buf)?)
-            },
-            _ => Err(Error::from_message("Invalid conversion")),
-        }
-    }
-
     fn get_daterec(&mut self, col: impl Into<ColumnId>) -> Result<Option<CS_DATEREC>> {
         let (fmt, buffer) = self.get_buffer(col)?;
         match buffer {
             None => Ok(None),
-            Some(buffer) => Ok(Some(self.convert_date(&fmt, buffer.deref())?)),
+            Some(buffer) => Ok(Some(convert_date(&mut self.conn, &fmt, buffer.deref())?)),
         }
     }
 
@@ -791,12 +1035,263 @@ impl ResultSet {
         }
     }
 
+    /*
+     * Output parameters returned by a stored procedure, in declaration order
+     */
+    pub fn outputs(&mut self) -> Result<Vec<(String, Value)>> {
+        let index = self.params_result()?;
+        let names: Vec<String> = Self::output_columns(&self.results[index])
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        let mut result = Vec::with_capacity(names.len());
+        for (i, name) in names.into_iter().enumerate() {
+            let value = self.get_output(i)?;
+            result.push((name, value));
+        }
+        Ok(result)
+    }
+
+    /*
+     * Value of an output parameter, by index or by name (with or without the leading '@')
+     * In streaming mode, results are read until the output parameters are found:
+     * unread rows of the result being streamed are discarded.
+     */
+    pub fn get_output(&mut self, col: impl Into<ColumnId>) -> Result<Value> {
+        let index = self.params_result()?;
+        let columns = Self::output_columns(&self.results[index]);
+        let col: usize = match Into::<ColumnId>::into(col) {
+            ColumnId::I32(i) => i.try_into()
+                .map_err(|_| Error::from_message("Invalid output parameter index"))?,
+            ColumnId::String(s) => columns.iter()
+                .position(|c| c.name.trim_start_matches('@') == s.trim_start_matches('@'))
+                .ok_or_else(|| Error::from_message(format!("Invalid output parameter name: {}", s)))?,
+        };
+        if col >= columns.len() {
+            return Err(Error::from_message("Invalid output parameter index"));
+        }
+
+        let (fmt, buffer) = match &self.results[index] {
+            SybResult::Params(params) => match params.rows.first() {
+                Some(row) => (params.columns[col].fmt.to_datafmt(), row.buffers[col].clone()),
+                None => return Err(Error::from_message("Invalid state")),
+            },
+            _ => return Err(Error::from_message("Invalid state")),
+        };
+
+        match buffer {
+            None => Ok(Value::Null),
+            Some(buffer) => buffer_to_value(&mut self.conn, &fmt, &buffer),
+        }
+    }
+
+    /*
+     * Index of the output parameters result in self.results,
+     * reading more results from the server in streaming mode
+     */
+    fn params_result(&mut self) -> Result<usize> {
+        let mut i = 0;
+        loop {
+            while i < self.results.len() {
+                if matches!(self.results[i], SybResult::Params(_)) {
+                    return Ok(i);
+                }
+                i += 1;
+            }
+            if !self.read_next_result() {
+                return Err(self.state_error("ResultSet does not contain output parameters"));
+            }
+        }
+    }
+
+    fn output_columns(result: &SybResult) -> &[Column] {
+        match result {
+            SybResult::Params(params) => &params.columns,
+            _ => &[],
+        }
+    }
+
     pub fn messages(&self) -> &Vec<Error> {
         &self.messages
     }
 
+    /*
+     * In streaming mode, an error raised while reading results takes precedence over messages
+     */
     pub fn error(&self) -> Option<Error> {
-        self.messages.first().cloned()
+        if self.stream_error.is_some() {
+            self.stream_error_reported.set(true);
+        }
+        self.stream_error.clone().or_else(|| self.messages.first().cloned())
     }
 }
 
+impl Drop for ResultSet {
+    fn drop(&mut self) {
+        if let Some(error) = self.stream_error.as_ref() {
+            if !self.stream_error_reported.get() {
+                warn!("Streamed results ended with an error which was not checked: {}", error);
+            }
+        }
+    }
+}
+
+pub(crate) fn buffer_to_value(conn: &mut Connection, fmt: &CS_DATAFMT, buffer: &[u8]) -> Result<Value> {
+    match fmt.datatype {
+        CS_BINARY_TYPE | CS_IMAGE_TYPE => Ok(Value::from(buffer)),
+        CS_CHAR_TYPE | CS_TEXT_TYPE => Ok(Value::from(
+            String::from_utf8_lossy(buffer).to_string(),
+        )),
+        CS_UNICHAR_TYPE => {
+            let dstfmt = CS_DATAFMT {
+                datatype: CS_CHAR_TYPE,
+                maxlength: buffer.len() as i32,
+                format: CS_FMT_UNUSED as i32,
+                count: 1,
+                ..Default::default()
+            };
+
+            let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
+            let dstlen = conn.convert(fmt, buffer, &dstfmt, &mut dstdata)?;
+            Ok(Value::from(
+                String::from_utf8_lossy(&dstdata.as_slice()[0..dstlen]).to_string(),
+            ))
+        }
+        CS_DATE_TYPE | CS_TIME_TYPE | CS_DATETIME_TYPE | CS_DATETIME4_TYPE => {
+            let datatype = fmt.datatype;
+            let daterec = convert_date(conn, fmt, buffer)?;
+            Ok(match datatype {
+                CS_DATE_TYPE => Value::from(
+                    NaiveDate::from_ymd_opt(
+                        daterec.dateyear,
+                        (daterec.datemonth + 1) as u32,
+                        daterec.datedmonth as u32,
+                    )
+                    .ok_or_else(|| Error::from_message("Invalid date"))?,
+                ),
+                CS_TIME_TYPE => Value::from(
+                    NaiveTime::from_hms_milli_opt(
+                        daterec.datehour as u32,
+                        daterec.dateminute as u32,
+                        daterec.datesecond as u32,
+                        daterec.datemsecond as u32,
+                    )
+                    .ok_or_else(|| Error::from_message("Invalid time"))?,
+                ),
+                CS_DATETIME_TYPE | CS_DATETIME4_TYPE => Value::from(
+                    NaiveDate::from_ymd_opt(
+                        daterec.dateyear,
+                        (daterec.datemonth + 1) as u32,
+                        daterec.datedmonth as u32,
+                    )
+                    .ok_or_else(|| Error::from_message("Invalid date"))?
+                    .and_hms_milli_opt(
+                        daterec.datehour as u32,
+                        daterec.dateminute as u32,
+                        daterec.datesecond as u32,
+                        daterec.datemsecond as u32,
+                    )
+                    .ok_or_else(|| Error::from_message("Invalid date"))?,
+                ),
+                _ => panic!("Invalid code path"),
+            })
+        }
+        CS_INT_TYPE => unsafe {
+            assert_eq!(buffer.len(), mem::size_of::<i32>());
+            let ptr: *const i32 = mem::transmute(buffer.as_ptr());
+            Ok(Value::from(*ptr))
+        },
+        CS_BIT_TYPE | CS_TINYINT_TYPE | CS_SMALLINT_TYPE => {
+            let dstfmt = CS_DATAFMT {
+                datatype: CS_INT_TYPE,
+                maxlength: mem::size_of::<i32>() as i32,
+                format: CS_FMT_UNUSED as i32,
+                count: 1,
+                ..Default::default()
+            };
+
+            let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
+            let dstlen = conn.convert(fmt, buffer, &dstfmt, &mut dstdata)?;
+            let value = dstdata
+                .get(..dstlen)
+                .and_then(|data| data.try_into().ok())
+                .map(i32::from_ne_bytes)
+                .ok_or_else(|| Error::from_message("Invalid integer conversion length"))?;
+            Ok(Value::from(value))
+        }
+        CS_MONEY_TYPE | CS_MONEY4_TYPE | CS_DECIMAL_TYPE | CS_NUMERIC_TYPE => {
+            if fmt.precision == CS_DEF_PREC && fmt.scale == 0 {
+                let dstfmt = CS_DATAFMT {
+                    datatype: CS_BIGINT_TYPE,
+                    maxlength: mem::size_of::<i64>() as i32,
+                    format: CS_FMT_UNUSED as i32,
+                    count: 1,
+                    ..Default::default()
+                };
+
+                let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
+                let dstlen = conn.convert(fmt, buffer, &dstfmt, &mut dstdata)?;
+                assert_eq!(dstlen, mem::size_of::<i64>());
+                unsafe {
+                    let ptr: *const i64 = mem::transmute(dstdata.as_ptr());
+                    Ok(Value::from(*ptr))
+                }
+            } else {
+                let dstfmt = CS_DATAFMT {
+                    datatype: CS_CHAR_TYPE,
+                    maxlength: 1024,
+                    format: CS_FMT_UNUSED as i32,
+                    precision: CS_SRC_VALUE,
+                    scale: CS_SRC_VALUE,
+                    count: 1,
+                    ..Default::default()
+                };
+
+                let mut dstdata: Vec<u8> = vec![0u8; dstfmt.maxlength as usize];
+                let dstlen = conn.convert(fmt, buffer, &dstfmt, &mut dstdata)?;
+                let s = String::from_utf8_lossy(&dstdata.as_slice()[0..dstlen]).to_string();
+                Ok(Value::from(
+                    Decimal::from_str_exact(&s)
+                        .map_err(|_| Error::from_message("Invalid decimal"))?,
+                ))
+            }
+        }
+        CS_REAL_TYPE => unsafe {
+            assert_eq!(buffer.len(), mem::size_of::<f32>());
+            let ptr: *const f32 = mem::transmute(buffer.as_ptr());
+            Ok(Value::from(Into::<f64>::into(*ptr)))
+        },
+        CS_FLOAT_TYPE => unsafe {
+            assert_eq!(buffer.len(), mem::size_of::<f64>());
+            let ptr: *const f64 = mem::transmute(buffer.as_ptr());
+            Ok(Value::from(*ptr))
+        },
+        _ => Err(Error::from_message("Unsupported datatype")),
+    }
+}
+
+pub(crate) fn convert_date(conn: &mut Connection, fmt: &CS_DATAFMT, buffer: &[u8]) -> Result<CS_DATEREC> {
+    match fmt.datatype {
+        CS_DATE_TYPE => unsafe {
+            assert!(buffer.len() == mem::size_of::<CS_DATE>());
+            let buf: *const CS_DATE = mem::transmute(buffer.as_ptr());
+            Ok(conn.crack_date(*buf)?)
+        },
+        CS_TIME_TYPE => unsafe {
+            assert!(buffer.len() == mem::size_of::<CS_TIME>());
+            let buf: *const CS_TIME = mem::transmute(buffer.as_ptr());
+            Ok(conn.crack_time(*buf)?)
+        },
+        CS_DATETIME_TYPE => unsafe {
+            assert!(buffer.len() == mem::size_of::<CS_DATETIME>());
+            let buf: *const CS_DATETIME = mem::transmute(buffer.as_ptr());
+            Ok(conn.crack_datetime(*buf)?)
+        },
+        CS_DATETIME4_TYPE => unsafe {
+            assert!(buffer.len() == mem::size_of::<CS_DATETIME4>());
+            let buf: *const CS_DATETIME4 = mem::transmute(buffer.as_ptr());
+            Ok(conn.crack_smalldatetime(*buf)?)
+        },
+        _ => Err(Error::from_message("Invalid conversion")),
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{Connection, ErrorKind, Result};
+use log::warn;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+
+/*
+ * How Connection::retry re-runs a closure which failed with a retryable error
+ * (see Error::is_retryable): deadlock victim or dead connection, and timeout
+ * if enabled with retry_timeouts.
+ * The delay before retry n is initial_backoff * multiplier^(n-1), capped to max_backoff.
+ * With jitter, a random delay between half and all of that is used instead.
+ */
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_timeouts: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+            retry_timeouts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /*
+     * Total number of runs, including the first one (default 3)
+     */
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /*
+     * Delay before the first retry (default 100ms)
+     */
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /*
+     * Upper bound of the delay between two runs (default 5s)
+     */
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /*
+     * Factor applied to the delay after each retry (default 2)
+     */
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /*
+     * Randomize delays, so that clients which failed together do not retry together (default true)
+     */
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /*
+     * Also retry after a timeout (default false).
+     * A statement which timed out may still have been run, or be running, on the server.
+     */
+    pub fn retry_timeouts(mut self, enabled: bool) -> Self {
+        self.retry_timeouts = enabled;
+        self
+    }
+
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /*
+     * Delay before the given retry, starting at 1
+     */
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exp);
+        let delay = if secs.is_finite() && secs < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max_backoff
+        };
+
+        if self.jitter {
+            /* RandomState is seeded randomly, which is enough here */
+            let random = RandomState::new().build_hasher().finish();
+            let fraction = (random >> 11) as f64 / (1u64 << 53) as f64;
+            delay.mul_f64(0.5 + fraction / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /*
+     * Run f until it succeeds, fails with an error which is not retryable,
+     * or max_attempts runs failed. The connection is reopened before a retry
+     * when it is no longer connected.
+     * f must be idempotent: it may run several times.
+     */
+    pub fn run<T, F>(&self, conn: &mut Connection, mut f: F) -> Result<T>
+    where
+        F: FnMut(&mut Connection) -> Result<T>,
+    {
+        let mut attempt = 1;
+        loop {
+            let e = match f(conn) {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            let retryable = match e.kind() {
+                ErrorKind::Timeout => self.retry_timeouts,
+                _ => e.is_retryable(),
+            };
+            if attempt >= self.max_attempts || !retryable {
+                return Err(e);
+            }
+
+            let delay = self.backoff(attempt);
+            attempt += 1;
+            warn!("Retrying in {:?} (attempt {}/{}): {}", delay, attempt, self.max_attempts, e);
+            thread::sleep(delay);
+
+            /* A failed reconnect uses up an attempt, f is not run on a dead connection */
+            while !conn.is_connected() {
+                warn!("Connection lost, reconnecting");
+                match conn.reconnect() {
+                    Ok(()) => break,
+                    Err(e) => {
+                        if attempt >= self.max_attempts {
+                            return Err(e);
+                        }
+                        let delay = self.backoff(attempt);
+                        attempt += 1;
+                        warn!("Reconnect failed, retrying in {:?} (attempt {}/{}): {}", delay, attempt, self.max_attempts, e);
+                        thread::sleep(delay);
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::error::Type;
+    use crate::tests::connect;
+    use crate::{Connection, Error, ErrorKind};
+    use std::time::Duration;
+
+    fn deadlock() -> Error {
+        Error {
+            type_: Type::Server,
+            code: Some(1205),
+            ..Error::from_message("Your server command encountered a deadlock situation")
+        }
+    }
+
+    fn timeout() -> Error {
+        Error {
+            type_: Type::Client,
+            code: Some(20003),
+            ..Error::from_message("Adaptive Server connection timed out")
+        }
+    }
+
+    fn spid(conn: &mut Connection) -> crate::Result<i32> {
+        let mut rs = conn.execute("select @@spid", &[])?;
+        assert!(rs.next());
+        rs.get_i32(0).map(Option::unwrap)
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .jitter(false);
+        assert_eq!(Duration::from_millis(100), policy.backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.backoff(2));
+        assert_eq!(Duration::from_millis(400), policy.backoff(3));
+        assert_eq!(Duration::from_millis(500), policy.backoff(4));
+        assert_eq!(Duration::from_millis(500), policy.backoff(100));
+
+        let policy = policy.jitter(true);
+        for retry in 1..5 {
+            let delay = policy.backoff(retry);
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let mut conn = connect();
+        let policy = RetryPolicy::new()
+            .max_attempts(3)
+            .initial_backoff(Duration::from_millis(1));
+
+        let mut runs = 0;
+        let value = conn
+            .retry_with(&policy, |conn| {
+                runs += 1;
+                if runs < 3 {
+                    return Err(deadlock());
+                }
+                let mut rs = conn.execute("select 42", &[])?;
+                assert!(rs.next());
+                rs.get_i32(0).map(Option::unwrap)
+            })
+            .unwrap();
+        assert_eq!(42, value);
+        assert_eq!(3, runs);
+
+        /* Gives up after max_attempts */
+        runs = 0;
+        let result: crate::Result<()> = conn.retry_with(&policy, |_| {
+            runs += 1;
+            Err(deadlock())
+        });
+        assert!(result.unwrap_err().is_deadlock());
+        assert_eq!(3, runs);
+
+        /* Other errors are not retried */
+        runs = 0;
+        let result = conn.retry_with(&policy, |conn| {
+            runs += 1;
+            conn.execute("select * from freetds_no_such_table", &[])
+        });
+        assert!(result.is_err());
+        assert_eq!(1, runs);
+
+        /* Timeouts are only retried on request */
+        runs = 0;
+        let result: crate::Result<()> = conn.retry_with(&policy, |_| {
+            runs += 1;
+            Err(timeout())
+        });
+        assert_eq!(ErrorKind::Timeout, result.unwrap_err().kind());
+        assert_eq!(1, runs);
+
+        runs = 0;
+        let policy = policy.retry_timeouts(true);
+        let result: crate::Result<()> = conn.retry_with(&policy, |_| {
+            runs += 1;
+            Err(timeout())
+        });
+        assert_eq!(ErrorKind::Timeout, result.unwrap_err().kind());
+        assert_eq!(3, runs);
+    }
+
+    #[test]
+    fn test_retry_reconnect() {
+        let mut conn = connect();
+        let old_spid = spid(&mut conn).unwrap();
+
+        /* Kill the connection from another one */
+        let mut other = connect();
+        other.execute(format!("kill {}", old_spid), &[]).unwrap();
+
+        let policy = RetryPolicy::new().initial_backoff(Duration::from_millis(1));
+        let mut errors = Vec::new();
+        let new_spid = conn
+            .retry_with(&policy, |conn| {
+                spid(conn).inspect_err(|e| errors.push(e.kind()))
+            })
+            .unwrap();
+        assert_eq!(vec![ErrorKind::ConnectionDead], errors);
+        assert!(conn.is_connected());
+        assert!(new_spid > 0);
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::expect_fun_call)]
 
-use crate::{parse_query, ColumnId, ParsedQuery, Value};
+use crate::{parse_query, ser, ColumnId, ParsedQuery, Result, Value};
+use serde::Serialize;
 
 pub struct Statement {
     pub(crate) text: String,
@@ -39,6 +40,19 @@ impl Statement {
         };
     }
 
+    /*
+     * Set the :name placeholders from the fields of a struct (or the entries of a map)
+     * implementing serde's Serialize. Fields without a matching placeholder are ignored.
+     */
+    pub fn bind<T: Serialize + ?Sized>(&mut self, params: &T) -> Result<()> {
+        for (name, value) in ser::to_params(params)? {
+            for i in self.query.param_index(&name) {
+                self.params[i] = value.clone();
+            }
+        }
+        Ok(())
+    }
+
     pub fn text(&self) -> &str {
         &self.text
     }
@@ -50,7 +64,7 @@ impl Statement {
 
 #[cfg(test)]
 mod tests {
-    use crate::{generate_query, to_sql::ToSql, Statement, Value};
+    use crate::{generate_query, to_sql::ToSql, Dialect, Statement, Value};
 
     #[test]
     fn test_set_param() {
@@ -93,7 +107,7 @@ mod tests {
             .map(|v| v as &dyn ToSql)
             .collect();
         let mut text = String::new();
-        generate_query(&mut text, &st.query, params.iter().map(|p| *p)).unwrap();
+        generate_query(&mut text, &st.query, params.iter().map(|p| *p), Dialect::Sybase).unwrap();
 
         let expected = "'DIO', 'ZA WARUDO', 'ZA WARUDO'";
         assert_eq!(expected, text);
@@ -1,12 +1,77 @@
 use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
 use rust_decimal::Decimal;
+use crate::{Error, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/*
+ * SQL flavour used when values are interpolated as literals into query text.
+ * Connections pick it from the server type, see ConnectionBuilder::dialect and Connection::server_type
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /* Sybase ASE: dates as 'yyyy/mm/dd', plain string literals */
+    #[default]
+    Sybase,
+
+    /*
+     * Microsoft SQL Server: ISO 8601 dates and times, which do not depend on
+     * the session language, and N'...' literals for strings which are not ASCII
+     */
+    Mssql,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dialect::Sybase => f.write_str("sybase"),
+            Dialect::Mssql => f.write_str("mssql"),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sybase" | "ase" => Ok(Dialect::Sybase),
+            "mssql" | "sqlserver" => Ok(Dialect::Mssql),
+            _ => Err(Error::from_message(format!("Invalid dialect: {}", s))),
+        }
+    }
+}
 
 pub trait ToSql {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result;
+
+    /*
+     * Literal for the given server flavour, to_sql writes the Sybase one
+     */
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, _dialect: Dialect) -> std::fmt::Result {
+        self.to_sql(f)
+    }
+
+    /*
+     * Value to send when the parameter is bound server-side (see ConnectionBuilder::server_side_params)
+     * Types which return None can only be used as interpolated literals
+     */
+    fn to_value(&self) -> Option<Value> {
+        None
+    }
 }
 
 fn write_string(f: &mut dyn std::fmt::Write, s: &str) -> std::fmt::Result {
+    write_string_dialect(f, s, Dialect::Sybase)
+}
+
+fn write_string_dialect(f: &mut dyn std::fmt::Write, s: &str, dialect: Dialect) -> std::fmt::Result {
     const QUOTE: char = '\'';
+
+    /* Plain literals are converted to the database code page, N'' keeps them as Unicode */
+    if dialect == Dialect::Mssql && !s.is_ascii() {
+        f.write_char('N')?;
+    }
     f.write_char(QUOTE)?;
     for c in s.chars() {
         f.write_char(c)?;
@@ -21,54 +86,135 @@ impl ToSql for &str {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write_string(f, self)
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        write_string_dialect(f, self, dialect)
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for String {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write_string(f, self)
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        write_string_dialect(f, self, dialect)
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(self.clone()))
+    }
+}
+
+/*
+ * bit columns take 1 and 0 on both servers
+ */
+impl ToSql for bool {
+    fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        f.write_str(if *self { "1" } else { "0" })
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self as i32))
+    }
 }
 
 impl ToSql for i32 {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write!(f, "{self}")
     }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for i64 {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write!(f, "{self}")
     }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for f64 {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write!(f, "{self}")
     }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for Decimal {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         write!(f, "{self}")
     }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for NaiveDate {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         f.write_str(&self.format("'%Y/%m/%d'").to_string())
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        match dialect {
+            Dialect::Sybase => self.to_sql(f),
+            /* yyyymmdd is the only date-only format that datetime reads the same in every language */
+            Dialect::Mssql => f.write_str(&self.format("'%Y%m%d'").to_string()),
+        }
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for NaiveTime {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         f.write_str(&self.format("'%H:%M:%S%.f'").to_string())
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        match dialect {
+            Dialect::Sybase => self.to_sql(f),
+            /* datetime rejects more than 3 fractional digits */
+            Dialect::Mssql => f.write_str(&self.format("'%H:%M:%S%.3f'").to_string()),
+        }
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for NaiveDateTime {
     fn to_sql(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         f.write_str(&self.format("'%Y/%m/%d %H:%M:%S%.f'").to_string())
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        match dialect {
+            Dialect::Sybase => self.to_sql(f),
+            /* datetime rejects more than 3 fractional digits */
+            Dialect::Mssql => f.write_str(&self.format("'%Y-%m-%dT%H:%M:%S%.3f'").to_string()),
+        }
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(*self))
+    }
 }
 
 impl ToSql for Vec<u8> {
@@ -79,6 +225,10 @@ impl ToSql for Vec<u8> {
         }
         Ok(())
     }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(Value::from(self.clone()))
+    }
 }
 
 impl<T> ToSql for Option<T>
@@ -91,5 +241,64 @@ where
             None => write!(f, "null"),
         }
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        match self {
+            Some(value) => value.to_sql_dialect(f, dialect),
+            None => write!(f, "null"),
+        }
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        match self {
+            Some(value) => value.to_value(),
+            None => Some(Value::Null),
+        }
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::{Dialect, ToSql};
+    use crate::Value;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn literal(value: &dyn ToSql, dialect: Dialect) -> String {
+        let mut text = String::new();
+        value.to_sql_dialect(&mut text, dialect).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_dialect() {
+        let date = NaiveDate::from_ymd_opt(1986, 7, 5).unwrap();
+        let datetime = date.and_hms_milli_opt(10, 30, 31, 250).unwrap();
+        assert_eq!("'1986/07/05'", literal(&date, Dialect::Sybase));
+        assert_eq!("'19860705'", literal(&date, Dialect::Mssql));
+        assert_eq!("'1986/07/05 10:30:31.250'", literal(&datetime, Dialect::Sybase));
+        assert_eq!("'1986-07-05T10:30:31.250'", literal(&datetime, Dialect::Mssql));
+        assert_eq!("'1986-07-05T10:30:31.250'", literal(&Some(datetime), Dialect::Mssql));
+        let datetime = date.and_hms_micro_opt(10, 30, 31, 250_500).unwrap();
+        assert_eq!("'1986-07-05T10:30:31.250'", literal(&datetime, Dialect::Mssql));
+        assert_eq!("'1986/07/05 10:30:31.250500'", literal(&datetime, Dialect::Sybase));
+        assert_eq!("'1986/07/05 10:30:31.250500'", literal(&Value::from(datetime), Dialect::Sybase));
+        assert_eq!("'1986-07-05T10:30:31.000'", literal(&date.and_hms_opt(10, 30, 31).unwrap(), Dialect::Mssql));
+
+        let time = NaiveTime::from_hms_nano_opt(10, 30, 31, 250_500_100).unwrap();
+        assert_eq!("'10:30:31.250500100'", literal(&time, Dialect::Sybase));
+        assert_eq!("'10:30:31.250'", literal(&time, Dialect::Mssql));
+        assert_eq!("'10:30:31.250'", literal(&Value::from(time), Dialect::Mssql));
+        assert_eq!("'10:30:31'", literal(&NaiveTime::from_hms_opt(10, 30, 31).unwrap(), Dialect::Sybase));
+
+        assert_eq!("'caf\u{e9}'", literal(&"caf\u{e9}", Dialect::Sybase));
+        assert_eq!("N'caf\u{e9}'", literal(&"caf\u{e9}", Dialect::Mssql));
+        assert_eq!("N'l''\u{e9}t\u{e9}'", literal(&String::from("l'\u{e9}t\u{e9}"), Dialect::Mssql));
+        assert_eq!("'plain'", literal(&"plain", Dialect::Mssql));
+
+        assert_eq!("1", literal(&true, Dialect::Mssql));
+        assert_eq!("0", literal(&false, Dialect::Sybase));
+        assert_eq!("null", literal(&None::<bool>, Dialect::Mssql));
+        assert_eq!("0xDEAD", literal(&vec![0xDE_u8, 0xAD], Dialect::Mssql));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{Connection, Error, Result};
+use log::warn;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SAVEPOINT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/*
+ * A transaction started by Connection::transaction
+ * The transaction is rolled back when the guard is dropped without calling commit,
+ * so that a panic or an early return never leaves the connection inside an open transaction.
+ * The connection remains usable through Deref/DerefMut while the transaction is active.
+ * A transaction started while another one is active is nested: it sets a savepoint instead,
+ * so that rolling it back only undoes its own changes. Committing it leaves the changes
+ * to the outer transaction.
+ */
+pub struct Transaction<'a> {
+    conn: &'a mut Connection,
+
+    /* @@trancount once the transaction is started */
+    depth: i32,
+
+    /* Savepoint of a nested transaction */
+    savepoint: Option<String>,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(conn: &'a mut Connection) -> Result<Self> {
+        let savepoint = if conn.trancount()? > 0 {
+            let name = format!("freetds_sp{}", SAVEPOINT_ID.fetch_add(1, Ordering::Relaxed));
+            conn.execute(format!("save tran {}", name), &[])?;
+            Some(name)
+        } else {
+            conn.execute("begin tran", &[])?;
+            None
+        };
+        let depth = conn.trancount()?;
+        Ok(Self {
+            conn,
+            depth,
+            savepoint,
+            finished: false,
+        })
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.finished = true;
+        self.check_active()?;
+        if self.savepoint.is_none() {
+            self.conn.execute("commit tran", &[])?;
+        }
+        Ok(())
+    }
+
+    pub fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        self.check_active()?;
+        self.conn.execute(self.rollback_text(), &[])?;
+        Ok(())
+    }
+
+    fn rollback_text(&self) -> String {
+        match self.savepoint.as_ref() {
+            Some(name) => format!("rollback tran {}", name),
+            None => String::from("rollback tran"),
+        }
+    }
+
+    /*
+     * Set a savepoint (save tran), which can later be restored with rollback_to
+     */
+    pub fn savepoint(&mut self, name: &str) -> Result<()> {
+        Self::check_name(name)?;
+        self.check_active()?;
+        self.conn.execute(format!("save tran {}", name), &[])?;
+        Ok(())
+    }
+
+    /*
+     * Roll back the changes made since the savepoint, the transaction remains active
+     */
+    pub fn rollback_to(&mut self, name: &str) -> Result<()> {
+        Self::check_name(name)?;
+        self.check_active()?;
+        self.conn.execute(format!("rollback tran {}", name), &[])?;
+        Ok(())
+    }
+
+    /*
+     * The server rolls back the whole transaction on some errors (deadlocks, ...),
+     * @@trancount tells whether it is still active
+     */
+    fn check_active(&mut self) -> Result<()> {
+        if self.conn.trancount()? < self.depth {
+            return Err(Error::from_message("Transaction is no longer active, it was rolled back by the server"));
+        }
+        Ok(())
+    }
+
+    fn check_name(name: &str) -> Result<()> {
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(Error::from_message(format!("Invalid savepoint name: {}", name)));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Deref for Transaction<'a> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn
+    }
+}
+
+impl<'a> DerefMut for Transaction<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        /* A nested transaction is only rolled back while the outer one is still active */
+        match self.conn.trancount() {
+            Ok(trancount) if trancount > 0 && (self.savepoint.is_none() || trancount >= self.depth) => {
+                let text = self.rollback_text();
+                if let Err(e) = self.conn.execute(&text, &[]) {
+                    warn!("{} failed: {}", text, e);
+                }
+            },
+            Ok(_) => {},
+            Err(e) => warn!("Cannot read @@trancount: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{connect, scratch_table};
+    use crate::Result;
+
+    fn setup() -> crate::Connection {
+        let mut conn = connect();
+        scratch_table(&mut conn, "freetds_tran", "id int");
+        conn
+    }
+
+    fn count(conn: &mut crate::Connection) -> i32 {
+        let mut rs = conn.execute("select count(*) from freetds_tran", &[]).unwrap();
+        assert!(rs.next());
+        rs.get_i32(0).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_commit_rollback() {
+        let mut conn = setup();
+
+        let mut tx = conn.transaction().unwrap();
+        tx.execute("insert into freetds_tran values(1)", &[]).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(1, count(&mut conn));
+        assert_eq!(0, conn.trancount().unwrap());
+
+        let mut tx = conn.transaction().unwrap();
+        tx.execute("insert into freetds_tran values(2)", &[]).unwrap();
+        tx.rollback().unwrap();
+        assert_eq!(1, count(&mut conn));
+
+        /* Rolled back on early return */
+        let insert = |conn: &mut crate::Connection| -> Result<()> {
+            let mut tx = conn.transaction()?;
+            tx.execute("insert into freetds_tran values(3)", &[])?;
+            tx.execute("insert into freetds_tran values('not an int')", &[])?;
+            tx.commit()
+        };
+        assert!(insert(&mut conn).is_err());
+        assert_eq!(1, count(&mut conn));
+        assert_eq!(0, conn.trancount().unwrap());
+    }
+
+    #[test]
+    fn test_savepoint() {
+        let mut conn = setup();
+
+        let mut tx = conn.transaction().unwrap();
+        tx.execute("insert into freetds_tran values(1)", &[]).unwrap();
+        tx.savepoint("sp1").unwrap();
+        tx.execute("insert into freetds_tran values(2)", &[]).unwrap();
+        tx.rollback_to("sp1").unwrap();
+        assert!(tx.savepoint("sp1; drop table freetds_tran").is_err());
+        tx.commit().unwrap();
+        assert_eq!(1, count(&mut conn));
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut conn = setup();
+
+        let mut tx = conn.transaction().unwrap();
+        tx.execute("insert into freetds_tran values(1)", &[]).unwrap();
+        {
+            let mut inner = tx.transaction().unwrap();
+            inner.execute("insert into freetds_tran values(2)", &[]).unwrap();
+            assert_eq!(1, inner.trancount().unwrap());
+        }
+        assert_eq!(1, count(&mut tx));
+
+        let mut inner = tx.transaction().unwrap();
+        inner.execute("insert into freetds_tran values(3)", &[]).unwrap();
+        inner.commit().unwrap();
+        let mut inner = tx.transaction().unwrap();
+        inner.execute("insert into freetds_tran values(4)", &[]).unwrap();
+        inner.rollback().unwrap();
+        assert_eq!(1, tx.trancount().unwrap());
+        tx.commit().unwrap();
+
+        assert_eq!(2, count(&mut conn));
+        assert_eq!(0, conn.trancount().unwrap());
+    }
+}
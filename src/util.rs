@@ -82,3 +82,95 @@ pub fn return_name(ret: i32) -> Option<&'static str> {
         _ => None,
     }
 }
+
+/*
+ * Decode %XX escapes, as found in the user, password and database parts of URLs
+ * Unlike form encoding, + is not a space: it is kept as is.
+ */
+pub(crate) fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            /* from_str_radix would also accept a sign, as in %+1 */
+            let hex = s.get(i + 1..i + 3).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+            result.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(result).ok()
+}
+
+pub(crate) fn percent_encode(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{:02X}", b));
+        }
+    }
+    result
+}
+
+/*
+ * [name], with ] doubled
+ */
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+/*
+ * Quote each part of a possibly qualified object name, like db.owner.table
+ * Empty parts, as in db..table, are kept. Temporary table names are not quoted,
+ * since ASE does not accept them as delimited identifiers, but must then be plain identifiers.
+ */
+pub(crate) fn quote_object_name(name: &str) -> String {
+    let is_plain = |part: &str| part.chars().all(|c| c.is_alphanumeric() || "_#@$".contains(c));
+    name.split('.')
+        .map(|part| {
+            if part.is_empty() || (part.starts_with('#') && is_plain(part)) {
+                String::from(part)
+            } else {
+                quote_identifier(part)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percent_decode, percent_encode, quote_identifier, quote_object_name};
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(Some(String::from("p@ss word")), percent_decode("p%40ss%20word"));
+        assert_eq!(Some(String::from("a+b+")), percent_decode("a+b%2B"));
+        assert_eq!(Some(String::from("caf\u{e9}")), percent_decode("caf%C3%A9"));
+        assert_eq!(None, percent_decode("%+1"));
+        assert_eq!(None, percent_decode("%4"));
+        assert_eq!(None, percent_decode("%zz"));
+        assert_eq!(None, percent_decode("%FF"));
+
+        let password = "a+b %/@:";
+        assert_eq!(Some(String::from(password)), percent_decode(&percent_encode(password)));
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!("[master]", quote_identifier("master"));
+        assert_eq!("[a]]; drop table x --]", quote_identifier("a]; drop table x --"));
+
+        assert_eq!("[freetds_bulk]", quote_object_name("freetds_bulk"));
+        assert_eq!("[master]..[freetds_bulk]", quote_object_name("master..freetds_bulk"));
+        assert_eq!("[master].[dbo].[freetds_bulk]", quote_object_name("master.dbo.freetds_bulk"));
+        assert_eq!("[t where 1=1; drop table x --]", quote_object_name("t where 1=1; drop table x --"));
+        assert_eq!("#freetds_bulk", quote_object_name("#freetds_bulk"));
+        assert_eq!("[#t where 1=1]", quote_object_name("#t where 1=1"));
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::{Display, Formatter, Write};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use rust_decimal::Decimal;
 use serde::{Serialize, Deserialize};
-use crate::to_sql::ToSql;
+use crate::to_sql::{Dialect, ToSql};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -35,6 +35,20 @@ impl ToSql for Value {
             Value::Blob(b) => b.to_sql(f),
         }
     }
+
+    fn to_sql_dialect(&self, f: &mut dyn std::fmt::Write, dialect: Dialect) -> std::fmt::Result {
+        match self {
+            Value::String(s) => s.to_sql_dialect(f, dialect),
+            Value::Date(d) => d.to_sql_dialect(f, dialect),
+            Value::Time(t) => t.to_sql_dialect(f, dialect),
+            Value::DateTime(dt) => dt.to_sql_dialect(f, dialect),
+            _ => self.to_sql(f),
+        }
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(self.clone())
+    }
 }
 
 impl Display for Value {